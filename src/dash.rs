@@ -0,0 +1,236 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    constrain_player_system, flap_input_system, gravity_system, player_flap_system,
+    steer_input_system, AudioAvailability, FlapEvent, GravityDirection, Player, PLAYER_SIZE,
+};
+
+/// Maximum time between two flaps for them to count as a double-tap.
+pub const DOUBLE_TAP_WINDOW: f32 = 0.25;
+pub const DASH_VELOCITY: f32 = 250.0;
+pub const DASH_COOLDOWN: f32 = 2.0;
+/// Forward speed the bird is pushed at for [`DASH_BOOST_TIME`] seconds after a dash.
+pub const DASH_BOOST_SPEED: f32 = 120.0;
+pub const DASH_BOOST_TIME: f32 = 0.15;
+
+const COOLDOWN_BAR_SIZE: Vec2 = Vec2::new(PLAYER_SIZE.x, 1.0);
+
+/// Unlocks the double-tap dash. Locked by default so classic play is unaffected.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct DashAbility {
+    pub unlocked: bool,
+}
+
+#[derive(Event, Default)]
+pub struct DashEvent;
+
+/// Present on an entity while one of its abilities is recharging. Removed once the timer finishes.
+#[derive(Component)]
+pub struct AbilityCooldown(pub Timer);
+
+/// Pushes the bird forward while the timer runs, overriding steering. Removed once it finishes.
+#[derive(Component)]
+pub struct DashBoost(pub Timer);
+
+/// Shrinking bar shown above an entity while it has an [`AbilityCooldown`].
+#[derive(Component)]
+pub struct CooldownBar;
+
+pub struct DashPlugin;
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DashEvent>()
+            .init_resource::<DashAbility>()
+            .add_systems(PreUpdate, dash_input_system.after(flap_input_system))
+            .add_systems(
+                Update,
+                dash_system.after(player_flap_system).before(gravity_system),
+            )
+            .add_systems(
+                Update,
+                dash_boost_system
+                    .after(dash_system)
+                    .after(steer_input_system)
+                    .before(constrain_player_system),
+            )
+            .add_systems(
+                Update,
+                (ability_cooldown_system, cooldown_ui_system).chain(),
+            );
+    }
+}
+
+pub fn dash_input_system(
+    mut flap_event: EventReader<FlapEvent>,
+    mut dash_writer: EventWriter<DashEvent>,
+    dash: Res<DashAbility>,
+    time: Res<Time>,
+    mut last_flap: Local<Option<f32>>,
+) {
+    if !dash.unlocked {
+        flap_event.clear();
+        return;
+    }
+
+    // Soft flaps from the hover assist aren't taps
+    if flap_event
+        .iter()
+        .filter(|flap| flap.strength >= 1.0)
+        .count()
+        > 0
+    {
+        let now = time.elapsed_seconds();
+        match *last_flap {
+            Some(last) if now - last <= DOUBLE_TAP_WINDOW => {
+                dash_writer.send_default();
+                // Don't let a triple-tap dash twice
+                *last_flap = None;
+            }
+            _ => *last_flap = Some(now),
+        }
+    }
+}
+
+fn dash_system(
+    mut query: Query<(Entity, &mut Player), Without<AbilityCooldown>>,
+    mut dash_event: EventReader<DashEvent>,
+    direction: Res<GravityDirection>,
+    audio: Res<AudioAvailability>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if let Ok((entity, mut player)) = query.get_single_mut() {
+        if dash_event.iter().count() > 0 {
+            player.y_vel = DASH_VELOCITY * direction.sign();
+            commands.entity(entity).insert((
+                AbilityCooldown(Timer::from_seconds(DASH_COOLDOWN, TimerMode::Once)),
+                DashBoost(Timer::from_seconds(DASH_BOOST_TIME, TimerMode::Once)),
+            ));
+            if audio.available {
+                commands.spawn(AudioBundle {
+                    source: asset_server.load("audio/sfx_swooshing.ogg"),
+                    settings: PlaybackSettings::DESPAWN,
+                });
+            }
+        }
+    } else {
+        dash_event.clear();
+    }
+}
+
+fn dash_boost_system(
+    mut query: Query<(Entity, &mut Player, &mut DashBoost)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut player, mut boost) in query.iter_mut() {
+        if boost.0.tick(time.delta()).finished() {
+            // Steering takes over again next frame
+            commands.entity(entity).remove::<DashBoost>();
+        } else {
+            player.x_vel = DASH_BOOST_SPEED;
+        }
+    }
+}
+
+fn ability_cooldown_system(
+    mut query: Query<(Entity, &mut AbilityCooldown)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut cooldown) in query.iter_mut() {
+        if cooldown.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<AbilityCooldown>();
+        }
+    }
+}
+
+fn cooldown_ui_system(
+    added: Query<Entity, Added<AbilityCooldown>>,
+    mut bars: Query<(&Parent, &mut Transform, &mut Visibility), With<CooldownBar>>,
+    cooldowns: Query<&AbilityCooldown>,
+    mut commands: Commands,
+) {
+    for (parent, mut transform, mut visibility) in bars.iter_mut() {
+        if let Ok(cooldown) = cooldowns.get(parent.get()) {
+            transform.scale.x = cooldown.0.percent_left();
+            visibility.set_if_neq(Visibility::Inherited);
+        } else {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+
+    // The bar is spawned the first time an entity starts a cooldown and is reused after that
+    for entity in added.iter() {
+        if bars.iter().any(|(parent, _, _)| parent.get() == entity) {
+            continue;
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(COOLDOWN_BAR_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        -COOLDOWN_BAR_SIZE.x / 2.0,
+                        PLAYER_SIZE.y / 2.0 + 2.0,
+                        0.1,
+                    ),
+                    ..default()
+                },
+                CooldownBar,
+            ));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::*, JUMP_VELOCITY};
+
+    #[test]
+    fn flap_and_dash_readers_both_see_each_flap() {
+        let mut app = player_app();
+        app.add_plugins(DashPlugin)
+            .insert_resource(DashAbility { unlocked: true });
+        start_game(&mut app);
+        player(&mut app).y_vel = -200.0;
+
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 60.0);
+        // The flap system saw the first tap
+        assert!(player(&mut app).y_vel > 0.0);
+
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 60.0);
+        // The dash input saw both taps, so the second one dashed instead of flapping
+        assert!(player(&mut app).y_vel > JUMP_VELOCITY);
+    }
+
+    #[test]
+    fn dash_boosts_the_bird_forward_briefly() {
+        let mut app = player_app();
+        app.add_plugins(DashPlugin)
+            .insert_resource(DashAbility { unlocked: true });
+        start_game(&mut app);
+        let start_x = player_transform(&mut app).translation.x;
+
+        app.world.send_event(DashEvent);
+        step(&mut app, 1.0 / 64.0);
+        // The boost is added at the end of the dash frame and wins over steering from the next
+        step(&mut app, 1.0 / 64.0);
+        assert_eq!(player(&mut app).x_vel, DASH_BOOST_SPEED);
+        assert!(player_transform(&mut app).translation.x > start_x);
+
+        for _ in 0..(DASH_BOOST_TIME * 64.0) as usize + 1 {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert_eq!(player(&mut app).x_vel, 0.0);
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    constrain_player_system, cursor_world_position, move_system, zoom_system, PlayArea, Player,
+    RenderScale, MAX_ZOOM, MIN_ZOOM,
+};
+
+pub const CURSOR_FOLLOW_KEY: KeyCode = KeyCode::F2;
+
+/// When enabled the bird sticks to the cursor and ignores gravity, for probing collisions by hand.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct CursorFollow {
+    pub enabled: bool,
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorFollow>()
+            .add_systems(
+                Update,
+                (toggle_cursor_follow_system, cursor_follow_system)
+                    .chain()
+                    .after(constrain_player_system)
+                    .after(move_system),
+            )
+            .add_systems(Update, zoom_input_system.before(zoom_system));
+    }
+}
+
+fn toggle_cursor_follow_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cursor_follow: ResMut<CursorFollow>,
+) {
+    if keyboard_input.just_pressed(CURSOR_FOLLOW_KEY) {
+        cursor_follow.enabled = !cursor_follow.enabled;
+        println!("Cursor follow: {}", cursor_follow.enabled);
+    }
+}
+
+fn cursor_follow_system(
+    mut player: Query<(&mut Transform, &mut Player)>,
+    main_window: Query<&Window, With<PrimaryWindow>>,
+    render_scale: Res<RenderScale>,
+    play_area: Res<PlayArea>,
+    cursor_follow: Res<CursorFollow>,
+) {
+    if !cursor_follow.enabled {
+        return;
+    }
+
+    if let (Ok((mut transform, mut player)), Ok(window)) =
+        (player.get_single_mut(), main_window.get_single())
+    {
+        if let Some(cursor) = cursor_world_position(window, &render_scale, &play_area) {
+            transform.translation.x = cursor.x;
+            transform.translation.y = cursor.y;
+        }
+        // Don't let gravity build up while the bird is held
+        player.x_vel = 0.0;
+        player.y_vel = 0.0;
+    }
+}
+
+/// Scrolling zooms the camera in and out.
+fn zoom_input_system(mut scroll: EventReader<MouseWheel>, mut render_scale: ResMut<RenderScale>) {
+    let delta: f32 = scroll
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y * 0.1,
+            MouseScrollUnit::Pixel => event.y * 0.001,
+        })
+        .sum();
+
+    if delta != 0.0 {
+        render_scale.zoom = (render_scale.zoom - delta).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
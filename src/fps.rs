@@ -0,0 +1,103 @@
+use std::fmt::Write;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hud::HudSettings;
+
+pub const FPS_TOGGLE_KEY: KeyCode = KeyCode::F3;
+const FPS_FONT_SIZE: f32 = 16.0;
+/// Distance from the top left corner of the window.
+const FPS_MARGIN: f32 = 4.0;
+
+/// Whether the FPS counter is shown. Off by default.
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FpsCounterSettings {
+    pub visible: bool,
+}
+
+#[derive(Component)]
+pub struct FpsText;
+
+pub struct FpsCounterPlugin;
+
+impl Plugin for FpsCounterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .init_resource::<FpsCounterSettings>()
+            .add_systems(Startup, spawn_fps_counter)
+            .add_systems(
+                Update,
+                (toggle_fps_counter_system, fps_counter_system).chain(),
+            );
+    }
+}
+
+fn spawn_fps_counter(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font_size: FPS_FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(FPS_MARGIN),
+            left: Val::Px(FPS_MARGIN),
+            ..default()
+        }),
+        FpsText,
+    ));
+}
+
+fn toggle_fps_counter_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<FpsCounterSettings>,
+) {
+    if keyboard_input.just_pressed(FPS_TOGGLE_KEY) {
+        settings.visible = !settings.visible;
+    }
+}
+
+fn fps_counter_system(
+    mut query: Query<(&mut Text, &mut Style, &mut Visibility), With<FpsText>>,
+    settings: Res<FpsCounterSettings>,
+    hud_settings: Res<HudSettings>,
+    diagnostics: Res<DiagnosticsStore>,
+) {
+    if let Ok((mut text, mut style, mut visibility)) = query.get_single_mut() {
+        if hud_settings.is_changed() {
+            let scale = hud_settings.scale();
+            text.sections[0].style.font_size = FPS_FONT_SIZE * scale;
+            style.top = Val::Px(FPS_MARGIN * scale);
+            style.left = Val::Px(FPS_MARGIN * scale);
+        }
+
+        if !settings.visible {
+            visibility.set_if_neq(Visibility::Hidden);
+            return;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let fps = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.smoothed());
+        let frame_time = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+            .and_then(|frame_time| frame_time.smoothed());
+
+        // Reuse the section's buffer instead of allocating a new string every frame
+        let value = &mut text.sections[0].value;
+        value.clear();
+        if let (Some(fps), Some(frame_time)) = (fps, frame_time) {
+            let _ = write!(value, "{:.0} fps {:.1} ms", fps, frame_time);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::{gravity_system, GameState, GravityDirection, Player};
+
+/// Seconds between each flip of gravity.
+pub const GRAVITY_FLIP_INTERVAL: f32 = 8.0;
+/// How long before a flip the bird starts blinking to warn of it.
+pub const GRAVITY_FLIP_WARNING: f32 = 1.0;
+/// Blinks per second while warning of a flip.
+const WARNING_BLINK_FREQUENCY: f32 = 6.0;
+const WARNING_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+/// Gimmick mode where gravity flips every [`GRAVITY_FLIP_INTERVAL`] seconds. Off in classic play.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct GravityFlipMode {
+    pub enabled: bool,
+}
+
+#[derive(Resource)]
+struct GravityFlipTimer(Timer);
+
+pub struct GravityFlipPlugin;
+
+impl Plugin for GravityFlipPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityFlipMode>()
+            .insert_resource(GravityFlipTimer(Timer::from_seconds(
+                GRAVITY_FLIP_INTERVAL,
+                TimerMode::Repeating,
+            )))
+            .add_systems(OnEnter(GameState::Game), reset_gravity_flip)
+            .add_systems(
+                Update,
+                gravity_flip_system
+                    .before(gravity_system)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(Update, gravity_cue_system.after(gravity_flip_system));
+    }
+}
+
+/// Every run starts with gravity pulling down.
+fn reset_gravity_flip(
+    mut timer: ResMut<GravityFlipTimer>,
+    mut direction: ResMut<GravityDirection>,
+) {
+    timer.0.reset();
+    direction.set_if_neq(GravityDirection::Down);
+}
+
+fn gravity_flip_system(
+    mode: Res<GravityFlipMode>,
+    mut timer: ResMut<GravityFlipTimer>,
+    mut direction: ResMut<GravityDirection>,
+    time: Res<Time>,
+) {
+    if !mode.enabled {
+        direction.set_if_neq(GravityDirection::Down);
+        return;
+    }
+
+    if timer.0.tick(time.delta()).just_finished() {
+        *direction = direction.flipped();
+    }
+}
+
+/// Turns the bird upside down while gravity is flipped and blinks it just before a flip.
+fn gravity_cue_system(
+    mut query: Query<&mut Sprite, With<Player>>,
+    mode: Res<GravityFlipMode>,
+    timer: Res<GravityFlipTimer>,
+    direction: Res<GravityDirection>,
+    state: Res<State<GameState>>,
+) {
+    if let Ok(mut sprite) = query.get_single_mut() {
+        let remaining = timer.0.remaining_secs();
+        let warning = mode.enabled
+            && *state.get() == GameState::Game
+            && remaining <= GRAVITY_FLIP_WARNING
+            && (remaining * WARNING_BLINK_FREQUENCY).fract() < 0.5;
+
+        sprite.flip_y = *direction == GravityDirection::Up;
+        sprite.color = if warning { WARNING_COLOR } else { Color::WHITE };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::*, GRAVITY};
+
+    #[test]
+    fn flipped_gravity_pulls_the_bird_up() {
+        let mut app = player_app();
+        app.add_plugins(GravityFlipPlugin)
+            .insert_resource(GravityFlipMode { enabled: true });
+        start_game(&mut app);
+
+        // Slightly past the first flip, so the bird has left the floor
+        let frames = (GRAVITY_FLIP_INTERVAL * 16.0) as usize + 2;
+        for _ in 0..frames {
+            step(&mut app, 1.0 / 16.0);
+        }
+        assert_eq!(
+            *app.world.resource::<GravityDirection>(),
+            GravityDirection::Up
+        );
+
+        let before = player(&mut app).y_vel;
+        step(&mut app, 1.0 / 16.0);
+        assert_eq!(player(&mut app).y_vel - before, -GRAVITY / 16.0);
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{z_layer, Physics, PlayArea, Player};
+
+const GAUGE_SIZE: Vec2 = Vec2::new(3.0, 60.0);
+/// Gap between the gauge and the edge of the play area.
+const GAUGE_MARGIN: f32 = 2.0;
+/// Fall speed shown as a full gauge when [`Physics::terminal_velocity`] is higher or uncapped.
+const GAUGE_MAX_FALL_SPEED: f32 = 400.0;
+
+pub const MIN_HUD_SCALE: f32 = 0.5;
+pub const MAX_HUD_SCALE: f32 = 3.0;
+
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudSettings {
+    /// Show a bar next to the play area that fills up or down with the bird's vertical velocity.
+    pub velocity_gauge: bool,
+    /// Size of HUD elements relative to normal, clamped to [`MIN_HUD_SCALE`]..=[`MAX_HUD_SCALE`].
+    pub hud_scale: f32,
+}
+
+impl Default for HudSettings {
+    fn default() -> Self {
+        Self {
+            velocity_gauge: false,
+            hud_scale: 1.0,
+        }
+    }
+}
+
+impl HudSettings {
+    pub fn scale(&self) -> f32 {
+        self.hud_scale.clamp(MIN_HUD_SCALE, MAX_HUD_SCALE)
+    }
+}
+
+#[derive(Component)]
+pub struct VelocityGauge;
+
+#[derive(Component)]
+pub struct VelocityGaugeFill;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudSettings>()
+            .add_systems(Startup, spawn_velocity_gauge)
+            .add_systems(
+                Update,
+                (velocity_gauge_layout_system, velocity_gauge_system),
+            );
+    }
+}
+
+/// Keeps the gauge the same distance from the edge of the play area whatever its scale.
+fn gauge_position(play_area: &PlayArea, scale: f32) -> Vec2 {
+    play_area.lerp((1.0, 0.5).into()) - Vec2::X * (GAUGE_SIZE.x / 2.0 + GAUGE_MARGIN) * scale
+}
+
+fn spawn_velocity_gauge(
+    mut commands: Commands,
+    play_area: Res<PlayArea>,
+    settings: Res<HudSettings>,
+) {
+    let scale = settings.scale();
+    let xy = gauge_position(&play_area, scale);
+
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.4),
+                    custom_size: Some(GAUGE_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(xy.x, xy.y, z_layer::HUD)
+                    .with_scale(Vec3::new(scale, scale, 1.0)),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            VelocityGauge,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(Vec2::new(GAUGE_SIZE.x, 0.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..default()
+                },
+                VelocityGaugeFill,
+            ));
+        });
+}
+
+fn velocity_gauge_layout_system(
+    mut gauge: Query<&mut Transform, With<VelocityGauge>>,
+    settings: Res<HudSettings>,
+    play_area: Res<PlayArea>,
+) {
+    if !settings.is_changed() && !play_area.is_changed() {
+        return;
+    }
+
+    if let Ok(mut transform) = gauge.get_single_mut() {
+        let scale = settings.scale();
+        let xy = gauge_position(&play_area, scale);
+        transform.translation.x = xy.x;
+        transform.translation.y = xy.y;
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+/// The gauge is centred on zero velocity. Its top half is a full flap and its bottom half is
+/// terminal velocity, or [`GAUGE_MAX_FALL_SPEED`] if that is lower.
+fn velocity_gauge_system(
+    mut gauge: Query<&mut Visibility, With<VelocityGauge>>,
+    mut fill: Query<(&mut Sprite, &mut Transform), With<VelocityGaugeFill>>,
+    player: Query<&Player>,
+    settings: Res<HudSettings>,
+    physics: Res<Physics>,
+) {
+    if let Ok(mut visibility) = gauge.get_single_mut() {
+        if !settings.velocity_gauge {
+            visibility.set_if_neq(Visibility::Hidden);
+            return;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+
+    if let (Ok((mut sprite, mut transform)), Ok(player)) =
+        (fill.get_single_mut(), player.get_single())
+    {
+        let max_fall_speed = physics.terminal_velocity.min(GAUGE_MAX_FALL_SPEED);
+        let y_vel = player.y_vel.clamp(-max_fall_speed, physics.jump_velocity);
+        let fraction = if y_vel >= 0.0 {
+            y_vel / physics.jump_velocity
+        } else {
+            y_vel / max_fall_speed
+        };
+
+        let height = fraction * GAUGE_SIZE.y / 2.0;
+        sprite.custom_size = Some(Vec2::new(GAUGE_SIZE.x, height.abs()));
+        transform.translation.y = height / 2.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    /// The gauge's scale and its gap from the right edge of the play area.
+    fn gauge_layout(app: &mut App) -> (f32, f32) {
+        let transform = *app
+            .world
+            .query_filtered::<&Transform, With<VelocityGauge>>()
+            .single(&app.world);
+        let edge = app.world.resource::<PlayArea>().lerp((1.0, 0.5).into()).x;
+        (transform.scale.x, edge - transform.translation.x)
+    }
+
+    #[test]
+    fn doubling_hud_scale_doubles_the_gauge() {
+        let mut app = player_app();
+        // Startup has already run, so spawn the gauge by hand
+        app.add_plugins(HudPlugin)
+            .add_systems(Update, spawn_velocity_gauge.run_if(run_once()));
+        step(&mut app, 1.0 / 60.0);
+        let (scale, gap) = gauge_layout(&mut app);
+
+        app.world.resource_mut::<HudSettings>().hud_scale = 2.0;
+        step(&mut app, 1.0 / 60.0);
+        assert_eq!(gauge_layout(&mut app), (scale * 2.0, gap * 2.0));
+    }
+}
@@ -1,12 +1,88 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::query::Has,
+    input::InputSystem,
+    prelude::*,
+    render::{texture::ImageSampler, view::screenshot::ScreenshotManager},
+    sprite::Anchor,
+    time::{Stopwatch, TimeSystem, TimeUpdateStrategy},
+    transform::TransformSystem,
+    utils::{Duration, Instant},
+    window::{PresentMode, PrimaryWindow},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+mod dash;
+#[cfg(feature = "debug")]
+mod debug;
+mod fps;
+mod gravity_flip;
+mod hud;
+mod particles;
+mod performance;
+mod settings;
+#[cfg(test)]
+mod testing;
+mod transition;
+mod z_layer;
+
+use dash::DashPlugin;
+use fps::FpsCounterPlugin;
+use gravity_flip::GravityFlipPlugin;
+use hud::HudPlugin;
+use particles::ParticlePlugin;
+use performance::PerformancePlugin;
+use settings::SettingsPlugin;
+use transition::{Transition, TransitionPlugin};
 
 pub const SCREEN_SCALE: f32 = 4.0;
 pub const BASE_RESOLUTION: Vec2 = Vec2 { x: 144.0, y: 200.0 };
 
+/// Longest frame gameplay systems will see. Longer frames, such as after the window was dragged
+/// or a browser tab was in the background, are shortened to this so nothing jumps.
+pub const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Sky colour of `city-background.png`, shown while the image loads.
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.482, 0.773, 0.804);
+
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 2.0;
+
+/// How much of the window the ground strip covers. The top of the ground is the floor of the
+/// play area.
+pub const GROUND_HEIGHT: f32 = 24.0;
+pub const GROUND_SIZE: Vec2 = Vec2::new(154.0, 56.0);
+
 pub const PLAYER_SIZE: Vec2 = Vec2::new(17.0, 12.0);
+/// Height, in world units, the bird bobs up and down by while idle.
+pub const BOB_AMPLITUDE: f32 = 3.0;
+/// Idle bobs per second.
+pub const BOB_FREQUENCY: f32 = 1.0;
+
+/// Largest change in playback speed, and so pitch, applied to each flap sound.
+pub const FLAP_PITCH_VARIATION: f32 = 0.08;
+
+/// Seconds each frame of the bird's flap animation is shown for.
+pub const BIRD_FRAME_TIME: f32 = 0.1;
+/// Order the bird sprites are shown in, as indices into [`BirdAnimation::frames`].
+const BIRD_FRAME_SEQUENCE: [usize; 4] = [0, 1, 2, 1];
 
 pub const GRAVITY: f32 = -650.0;
 pub const JUMP_VELOCITY: f32 = 150.0;
+/// Gravity is multiplied by this while the bird is diving.
+pub const DIVE_GRAVITY_MULTIPLIER: f32 = 2.5;
+/// Fastest a dive can fall, even when [`Physics::terminal_velocity`] is higher or uncapped.
+pub const DIVE_TERMINAL_VELOCITY: f32 = 400.0;
+pub const STEER_SPEED: f32 = 60.0;
+/// Seconds between the soft flaps sent while the hover assist is held.
+pub const HOVER_FLAP_INTERVAL: f32 = 0.25;
+/// Fraction of a normal flap's velocity given by each hover assist flap.
+pub const HOVER_FLAP_STRENGTH: f32 = 0.5;
+
+/// Seconds into a run over which [`SoftStart`] eases flaps up to full strength.
+pub const SOFT_START_WINDOW: f32 = 0.5;
+/// Strength of a flap at the very start of a run with [`SoftStart`] on.
+pub const SOFT_START_STRENGTH: f32 = 0.6;
 
 // pub const DEFAULT_AUDIO_SETTINGS: PlaybackSettings = PlaybackSettings {
 //     volume: bevy::audio::Volume::Relative(VolumeLevel::new(0.1)),
@@ -21,7 +97,7 @@ fn main() {
                     primary_window: Some(Window {
                         title: "Flappy Bird!".to_owned(),
                         resolution: (BASE_RESOLUTION * SCREEN_SCALE).into(),
-                        present_mode: bevy::window::PresentMode::AutoVsync,
+                        present_mode: PresentMode::AutoVsync,
                         // Tells wasm to resize the window according to the available canvas
                         fit_canvas_to_parent: false,
                         // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
@@ -34,12 +110,81 @@ fn main() {
                 .set(ImagePlugin::default_nearest()),
         )
         .insert_resource(GlobalVolume::new(0.2))
+        .insert_resource(ClearColor(BACKGROUND_COLOR))
         .add_state::<GameState>()
-        .add_plugins(PlayerPlugin)
-        .add_systems(Startup, (spawn_camera, spawn_background))
+        .init_resource::<PlayArea>()
+        .add_plugins(SettingsPlugin)
+        .init_resource::<Accessibility>()
+        .init_resource::<AudioAvailability>()
+        .add_plugins((
+            PlayerPlugin,
+            DashPlugin,
+            ParticlePlugin,
+            FpsCounterPlugin,
+            HudPlugin,
+            PerformancePlugin,
+            GravityFlipPlugin,
+            TransitionPlugin,
+        ))
+        .add_plugins(
+            #[cfg(feature = "debug")]
+            debug::DebugPlugin,
+            #[cfg(not(feature = "debug"))]
+            (),
+        )
+        .add_systems(First, cap_frame_time_system.before(TimeSystem))
+        .add_systems(Startup, (spawn_camera, spawn_background, spawn_ground))
+        .add_systems(
+            Update,
+            (
+                screenshot_system,
+                apply_display_settings_system,
+                image_filtering_system,
+                audio_availability_system,
+            ),
+        )
+        .add_systems(Update, zoom_system)
+        .add_systems(
+            PostUpdate,
+            (
+                unsnap_system.before(TransformSystem::TransformPropagate),
+                pixel_snap_system.after(TransformSystem::TransformPropagate),
+            ),
+        )
         .run();
 }
 
+/// Release builds skip the frame time log.
+fn debug_build() -> bool {
+    cfg!(debug_assertions)
+}
+
+/// Advances [`Time`] by the real frame time, but slows it down for a frame longer than
+/// [`MAX_FRAME_TIME`] so gameplay moves by no more than that. The raw delta stays real, so frame
+/// time diagnostics still see the stall.
+fn cap_frame_time_system(mut time: ResMut<Time>, mut update_strategy: ResMut<TimeUpdateStrategy>) {
+    let now = Instant::now();
+    let frame_time = time
+        .last_update()
+        .map_or(Duration::ZERO, |last_update| now - last_update);
+
+    if debug_build() && frame_time > MAX_FRAME_TIME {
+        println!("Clamped {:?} frame to {:?}", frame_time, MAX_FRAME_TIME);
+    }
+
+    *update_strategy = TimeUpdateStrategy::ManualInstant(now);
+    time.set_relative_speed_f64(frame_time_speed(frame_time));
+}
+
+/// How fast the game clock runs for a frame that really took `frame_time`.
+fn frame_time_speed(frame_time: Duration) -> f64 {
+    if frame_time > MAX_FRAME_TIME {
+        MAX_FRAME_TIME.as_secs_f64() / frame_time.as_secs_f64()
+    } else {
+        1.0
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
@@ -48,129 +193,1009 @@ pub enum GameState {
     GameOver,
 }
 
-pub fn spawn_camera(mut commands: Commands) {
-    let xy = lerp_window((0.5, 0.5).into());
+/// The region of the world the bird is allowed to play in. The margins reserve
+/// space at the top and bottom of the window for HUD elements and the ground.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PlayArea {
+    pub size: Vec2,
+    pub top_margin: f32,
+    pub bottom_margin: f32,
+}
+
+impl Default for PlayArea {
+    fn default() -> Self {
+        Self {
+            size: BASE_RESOLUTION,
+            top_margin: 0.0,
+            bottom_margin: GROUND_HEIGHT,
+        }
+    }
+}
+
+impl PlayArea {
+    pub fn bottom(&self) -> f32 {
+        self.bottom_margin
+    }
+
+    pub fn top(&self) -> f32 {
+        self.size.y - self.top_margin
+    }
+
+    /// Like [`lerp_window`], but maps `uv.y` between the bottom and top of the play area.
+    pub fn lerp(&self, uv: Vec2) -> Vec2 {
+        Vec2 {
+            x: lerp(uv.x, 0.0, self.size.x),
+            y: lerp(uv.y, self.bottom(), self.top()),
+        }
+    }
+}
+
+pub fn spawn_camera(
+    mut commands: Commands,
+    render_scale: Res<RenderScale>,
+    play_area: Res<PlayArea>,
+) {
+    let xy = render_scale.camera_position(&play_area);
     println!("Camera spawned at {}", xy);
     commands.spawn(Camera2dBundle {
         projection: OrthographicProjection {
-            scale: 1.0 / SCREEN_SCALE,
+            scale: render_scale.projection_scale(),
             ..default()
         },
-        transform: Transform::from_xyz(xy.x, xy.y, 0.0),
+        transform: Transform::from_xyz(xy.x, xy.y, z_layer::CAMERA),
         ..default()
     });
 }
 
+/// Zoom applied on top of [`SCREEN_SCALE`]. Values above `1.0` show a larger view.
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderScale {
+    /// Only changed from the debug zoom input, so it isn't saved.
+    #[serde(skip)]
+    pub zoom: f32,
+    /// Moves the camera up from the centre of the screen, so positive values sit the bird lower.
+    /// Clamped to [`RenderScale::camera_offset_range`].
+    pub camera_offset_y: f32,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            camera_offset_y: 0.0,
+        }
+    }
+}
+
+impl RenderScale {
+    /// World units per logical window pixel.
+    pub fn projection_scale(&self) -> f32 {
+        self.zoom.clamp(MIN_ZOOM, MAX_ZOOM) / SCREEN_SCALE
+    }
+
+    /// Half the height of the world the camera shows.
+    fn view_half_height(&self) -> f32 {
+        BASE_RESOLUTION.y * SCREEN_SCALE / 2.0 * self.projection_scale()
+    }
+
+    /// Offsets that keep both the floor and the top of `play_area` on screen at the current zoom.
+    /// When the view is too short to show both, the camera stays halfway between the two limits.
+    pub fn camera_offset_range(&self, play_area: &PlayArea) -> (f32, f32) {
+        let centre = lerp_window((0.5, 0.5).into()).y;
+        let min = play_area.top() - self.view_half_height() - centre;
+        let max = play_area.bottom() + self.view_half_height() - centre;
+        if min <= max {
+            (min, max)
+        } else {
+            let middle = (min + max) / 2.0;
+            (middle, middle)
+        }
+    }
+
+    pub fn camera_position(&self, play_area: &PlayArea) -> Vec2 {
+        let (min, max) = self.camera_offset_range(play_area);
+        let offset_y = self.camera_offset_y.clamp(min, max);
+        lerp_window((0.5, 0.5).into()) + Vec2::new(0.0, offset_y)
+    }
+}
+
+pub fn zoom_system(
+    render_scale: Res<RenderScale>,
+    play_area: Res<PlayArea>,
+    mut camera: Query<(&mut OrthographicProjection, &mut Transform), With<Camera>>,
+) {
+    if !render_scale.is_changed() && !play_area.is_changed() {
+        return;
+    }
+
+    if let Ok((mut projection, mut transform)) = camera.get_single_mut() {
+        projection.scale = render_scale.projection_scale();
+
+        let xy = render_scale.camera_position(&play_area);
+        transform.translation.x = xy.x;
+        transform.translation.y = xy.y;
+    }
+}
+
 fn spawn_background(mut commands: Commands, asset_server: Res<AssetServer>) {
     let xy = lerp_window((0.5, 0.5).into());
     println!("Background spawned at {}", xy);
 
     commands.spawn(SpriteBundle {
-        transform: Transform::from_xyz(xy.x, xy.y, -1.0),
-        texture: asset_server.load("sprites/city-background.png"),
+        transform: Transform::from_xyz(xy.x, xy.y, z_layer::BACKGROUND),
+        texture: load_sprite(&asset_server, "city-background.png"),
+        ..default()
+    });
+}
+
+fn spawn_ground(mut commands: Commands, asset_server: Res<AssetServer>, play_area: Res<PlayArea>) {
+    // The sprite is taller than the ground strip; the rest hangs off the bottom of the screen
+    let y = play_area.bottom() - GROUND_SIZE.y / 2.0;
+
+    commands.spawn(SpriteBundle {
+        transform: Transform::from_xyz(play_area.size.x / 2.0, y, z_layer::GROUND),
+        texture: load_sprite(&asset_server, "ground.png"),
         ..default()
     });
 }
 
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// One of [`PresentMode::AutoVsync`], [`PresentMode::Immediate`] or [`PresentMode::Fifo`].
+    pub present_mode: PresentMode,
+    /// Use linear instead of nearest filtering for images. Off by default to keep the pixel-art look.
+    pub smooth_filtering: bool,
+    /// Round sprites to whole screen pixels when drawing them so they don't shimmer.
+    pub pixel_snap: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentMode::AutoVsync,
+            smooth_filtering: false,
+            pixel_snap: true,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct Accessibility {
+    /// Disables purely cosmetic motion such as background particles.
+    pub reduced_motion: bool,
+    /// Assist: holding the flap input keeps sending soft flaps so the bird roughly hovers.
+    pub hold_to_hover: bool,
+}
+
+fn apply_display_settings_system(
+    settings: Res<DisplaySettings>,
+    mut main_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Ok(mut window) = main_window.get_single_mut() {
+        window.present_mode = settings.present_mode;
+    }
+}
+
+fn image_filtering_system(
+    settings: Res<DisplaySettings>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sampler = if settings.smooth_filtering {
+        ImageSampler::linear()
+    } else {
+        ImageSampler::nearest()
+    };
+
+    if settings.is_changed() {
+        image_events.clear();
+        for (_, image) in images.iter_mut() {
+            image.sampler_descriptor = sampler.clone();
+        }
+        return;
+    }
+
+    for event in image_events.iter() {
+        if let AssetEvent::Created { handle } = event {
+            if let Some(image) = images.get_mut(handle) {
+                image.sampler_descriptor = sampler.clone();
+            }
+        }
+    }
+}
+
+/// Bevy only recomputes a [`GlobalTransform`] when its [`Transform`] changes, so a sprite that
+/// stands still would otherwise be snapped again from last frame's snapped position and drift
+/// whenever the grid moves. Marking every sprite as moved makes each snap start from its real
+/// position, and puts them back where they belong when snapping is turned off.
+fn unsnap_system(settings: Res<DisplaySettings>, mut query: Query<&mut Transform, With<Sprite>>) {
+    if !settings.pixel_snap && !settings.is_changed() {
+        return;
+    }
+
+    for mut transform in query.iter_mut() {
+        transform.set_changed();
+    }
+}
+
+/// Snaps the rendered position of sprites to the screen pixel grid. Only the [`GlobalTransform`]
+/// is rounded, so the [`Transform`] gameplay works with never accumulates rounding error.
+fn pixel_snap_system(
+    settings: Res<DisplaySettings>,
+    render_scale: Res<RenderScale>,
+    play_area: Res<PlayArea>,
+    mut query: Query<&mut GlobalTransform, With<Sprite>>,
+) {
+    if !settings.pixel_snap {
+        return;
+    }
+
+    // The centre of the window is on a pixel boundary, so the grid is measured from the camera
+    let pixel = render_scale.projection_scale();
+    let camera = render_scale.camera_position(&play_area);
+    for mut global_transform in query.iter_mut() {
+        let mut affine = global_transform.affine();
+        affine.translation.x =
+            camera.x + ((affine.translation.x - camera.x) / pixel).round() * pixel;
+        affine.translation.y =
+            camera.y + ((affine.translation.y - camera.y) / pixel).round() * pixel;
+        *global_transform = affine.into();
+    }
+}
+
+/// Whether sounds can be played. Without an audio device Bevy never plays or despawns sound
+/// entities, so systems check this before spawning one.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AudioAvailability {
+    pub available: bool,
+}
+
+impl Default for AudioAvailability {
+    fn default() -> Self {
+        Self { available: true }
+    }
+}
+
+/// Bevy doesn't expose whether it found an audio device, so this watches for a sound that has
+/// loaded but still isn't playing a frame later.
+fn audio_availability_system(
+    query: Query<(Entity, &Handle<AudioSource>), Without<AudioSink>>,
+    sources: Res<Assets<AudioSource>>,
+    mut availability: ResMut<AudioAvailability>,
+    mut commands: Commands,
+    mut waiting: Local<Vec<Entity>>,
+) {
+    if !availability.available {
+        return;
+    }
+
+    let loaded: Vec<Entity> = query
+        .iter()
+        .filter(|(_, source)| sources.contains(*source))
+        .map(|(entity, _)| entity)
+        .collect();
+
+    if loaded.iter().any(|entity| waiting.contains(entity)) {
+        println!("No audio output available, sounds are disabled");
+        availability.available = false;
+        for (entity, _) in query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    *waiting = loaded;
+}
+
+/// Saves the current frame as a PNG when F12 is pressed. On wasm the image is downloaded instead.
+fn screenshot_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    main_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    time: Res<Time>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    if let Ok(window) = main_window.get_single() {
+        let path = format!("screenshot-{}.png", time.elapsed().as_millis());
+        match screenshot_manager.save_screenshot_to_disk(window, &path) {
+            Ok(()) => println!("Saved screenshot to {}", path),
+            Err(e) => println!("Could not take screenshot: {}", e),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Player {
+    x_vel: f32,
     y_vel: f32,
 }
 
+/// Lets the player hold a key to make the bird fall faster. Off in classic play.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct DiveMode {
+    pub enabled: bool,
+}
+
+/// Nudges the drawn bird relative to its position, which is also the centre of its hitbox.
+/// Lets the art be lined up without changing how the bird collides. In world units before
+/// [`BirdSize`] is applied, whatever the size of the bird's image.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct BirdSpriteOffset {
+    pub offset: Vec2,
+}
+
+/// Present on the bird while the dive input is held.
+#[derive(Component)]
+pub struct Diving;
+
+/// Scales the bird's sprite and hitbox together. Bigger is easier to see but harder to fit
+/// through gaps.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BirdSize {
+    pub scale: f32,
+}
+
+impl Default for BirdSize {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+impl BirdSize {
+    /// Size of the bird's hitbox.
+    pub fn size(&self) -> Vec2 {
+        PLAYER_SIZE * self.scale
+    }
+}
+
+/// Which way gravity pulls the bird. Only ever flipped by the gravity flip gimmick.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GravityDirection {
+    #[default]
+    Down,
+    Up,
+}
+
+impl GravityDirection {
+    /// `1.0` when gravity pulls down. Multiplying a vertical velocity by this converts it to and
+    /// from one where falling is always negative.
+    pub fn sign(self) -> f32 {
+        match self {
+            GravityDirection::Down => 1.0,
+            GravityDirection::Up => -1.0,
+        }
+    }
+
+    pub fn flipped(self) -> Self {
+        match self {
+            GravityDirection::Down => GravityDirection::Up,
+            GravityDirection::Up => GravityDirection::Down,
+        }
+    }
+}
+
+/// Makes flaps right after leaving the menu gentler so the bird isn't launched up the screen.
+/// Off in classic play.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct SoftStart {
+    pub enabled: bool,
+}
+
+/// Time spent in [`GameState::Game`] this run.
+#[derive(Resource, Debug, Default)]
+pub struct RunTimer(pub Stopwatch);
+
+/// Lets the player steer the bird left and right. Off in classic play.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct FreeFlyMode {
+    pub enabled: bool,
+}
+
+/// Tunable values for the bird's movement.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Physics {
+    pub gravity: f32,
+    pub jump_velocity: f32,
+    /// Fastest the bird can fall, as a positive speed. `f32::INFINITY` leaves the fall uncapped.
+    pub terminal_velocity: f32,
+    /// Fraction of the bird's vertical velocity lost per second. `0.0` disables drag. Steering
+    /// sets the horizontal velocity outright every frame, so there is no sideways momentum to
+    /// slow down.
+    pub drag: f32,
+    /// Vertical velocity given to the bird when a game starts.
+    pub start_velocity: f32,
+    pub flap_mode: FlapMode,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: GRAVITY,
+            jump_velocity: JUMP_VELOCITY,
+            terminal_velocity: f32::INFINITY,
+            drag: 0.0,
+            start_velocity: 0.0,
+            flap_mode: FlapMode::SetVelocity,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlapMode {
+    /// Flapping sets the bird's velocity to `jump_velocity`, cancelling any fall.
+    #[default]
+    SetVelocity,
+    /// Flapping adds `jump_velocity` to the bird's velocity, capped at `jump_velocity`.
+    AddImpulse,
+}
+
+impl FlapMode {
+    pub fn apply(self, y_vel: f32, jump_velocity: f32) -> f32 {
+        match self {
+            FlapMode::SetVelocity => jump_velocity,
+            FlapMode::AddImpulse => (y_vel + jump_velocity).min(jump_velocity),
+        }
+    }
+}
+
+/// Named sets of [`Physics`] values. Changing the selected preset overwrites [`Physics`].
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhysicsPreset {
+    /// The original feel, with no cap on fall speed.
+    #[default]
+    Classic,
+    /// Floatier and more forgiving.
+    Modern,
+}
+
+impl PhysicsPreset {
+    pub fn physics(self) -> Physics {
+        match self {
+            PhysicsPreset::Classic => Physics::default(),
+            PhysicsPreset::Modern => Physics {
+                gravity: -450.0,
+                jump_velocity: 130.0,
+                terminal_velocity: 250.0,
+                drag: 0.5,
+                start_velocity: JUMP_VELOCITY / 2.0,
+                flap_mode: FlapMode::AddImpulse,
+            },
+        }
+    }
+}
+
+pub fn apply_physics_preset(preset: Res<PhysicsPreset>, mut physics: ResMut<Physics>) {
+    if preset.is_changed() {
+        *physics = preset.physics();
+    }
+}
+
 #[derive(Component)]
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FlapEvent>()
+            .init_resource::<Physics>()
+            .init_resource::<PhysicsPreset>()
+            .init_resource::<FreeFlyMode>()
+            .init_resource::<DiveMode>()
+            .init_resource::<SoftStart>()
+            .init_resource::<GravityDirection>()
+            .init_resource::<BirdSize>()
+            .init_resource::<BirdSpriteOffset>()
+            .init_resource::<RunTimer>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<ConstrainMode>()
             .add_systems(Startup, spawn_player)
+            .add_systems(
+                OnEnter(GameState::Game),
+                (
+                    start_velocity_system,
+                    reset_run_timer,
+                    set_constrain_mode(ConstrainMode::PlayArea),
+                ),
+            )
+            .add_systems(
+                Update,
+                run_timer_system
+                    .before(player_flap_system)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                (start_bobbing, set_constrain_mode(ConstrainMode::Free)),
+            )
+            .add_systems(OnExit(GameState::Menu), stop_bobbing)
+            .add_systems(Update, idle_bob_system.after(player_flap_system))
+            .add_systems(Update, start_game_system.run_if(in_state(GameState::Menu)))
             .add_systems(Update, move_system)
-            .add_systems(Update, flap_input_system)
-            .add_systems(Update, player_flap_system.after(flap_input_system))
+            // Read input as early as possible so a flap is applied on the same frame.
+            .add_systems(PreUpdate, flap_input_system.after(InputSystem))
+            .add_systems(PreUpdate, dive_input_system.after(InputSystem))
+            .add_systems(Update, player_flap_system.before(gravity_system))
             .add_systems(Update, gravity_system.before(constrain_player_system))
+            .add_systems(Update, apply_physics_preset.before(player_flap_system))
             .add_systems(Update, constrain_player_system.before(move_system))
+            .add_systems(Update, steer_input_system.before(constrain_player_system))
+            .add_systems(Update, bird_animation_system)
+            .add_systems(Update, bird_size_system)
+            .add_systems(Update, bird_sprite_offset_system)
             .add_systems(Update, debug_on_press);
     }
 }
 
-fn spawn_player(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let xy = lerp_window((1.0 / 2.0, 0.5).into());
+fn spawn_player(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    play_area: Res<PlayArea>,
+    bird_size: Res<BirdSize>,
+) {
+    let xy = play_area.lerp((1.0 / 2.0, 0.5).into());
     println!("Player spawned at {}", xy);
 
     commands.spawn((
         SpriteBundle {
-            transform: Transform::from_xyz(xy.x, xy.y, 0.0),
-            texture: asset_server.load("sprites/bird-0.png"),
+            sprite: Sprite::default(),
+            transform: Transform::from_xyz(xy.x, xy.y, z_layer::PLAYER).with_scale(Vec3::new(
+                bird_size.scale,
+                bird_size.scale,
+                1.0,
+            )),
+            texture: load_sprite(&asset_server, "bird-0.png"),
             ..default()
         },
-        Player { y_vel: 0.0 },
+        Player {
+            x_vel: 0.0,
+            y_vel: 0.0,
+        },
+        BirdAnimation {
+            frames: ["bird-0.png", "bird-1.png", "bird-2.png"]
+                .map(|name| load_sprite(&asset_server, name)),
+            step: 0,
+        },
+        AnimationTimer(Timer::from_seconds(BIRD_FRAME_TIME, TimerMode::Repeating)),
     ));
 }
 
-pub fn gravity_system(mut query: Query<&mut Player>, time: Res<Time>) {
+fn bird_size_system(mut query: Query<&mut Transform, With<Player>>, bird_size: Res<BirdSize>) {
+    if !bird_size.is_changed() {
+        return;
+    }
+
+    for mut transform in query.iter_mut() {
+        transform.scale = Vec3::new(bird_size.scale, bird_size.scale, 1.0);
+    }
+}
+
+/// Runs every frame because the anchor depends on the image, which may load late and can be any
+/// size if it is a custom sprite.
+fn bird_sprite_offset_system(
+    mut query: Query<(&mut Sprite, &Handle<Image>), With<Player>>,
+    sprite_offset: Res<BirdSpriteOffset>,
+    images: Res<Assets<Image>>,
+) {
+    for (mut sprite, texture) in query.iter_mut() {
+        if let Some(image) = images.get(texture) {
+            // Anchors are in fractions of the sprite's size and move the opposite way
+            let anchor = -sprite_offset.offset / image.size();
+            if sprite.anchor.as_vec() != anchor {
+                sprite.anchor = Anchor::Custom(anchor);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BirdAnimation {
+    frames: [Handle<Image>; 3],
+    /// Position in [`BIRD_FRAME_SEQUENCE`].
+    step: usize,
+}
+
+#[derive(Component)]
+pub struct AnimationTimer(pub Timer);
+
+/// Advances by however many frames fit in the elapsed time, so the flap cadence doesn't depend
+/// on the frame rate.
+pub fn bird_animation_system(
+    mut query: Query<(&mut Handle<Image>, &mut BirdAnimation, &mut AnimationTimer)>,
+    time: Res<Time>,
+) {
+    for (mut texture, mut animation, mut timer) in query.iter_mut() {
+        let frames_elapsed = timer.0.tick(time.delta()).times_finished_this_tick() as usize;
+        if frames_elapsed == 0 {
+            continue;
+        }
+
+        animation.step = (animation.step + frames_elapsed) % BIRD_FRAME_SEQUENCE.len();
+        *texture = animation.frames[BIRD_FRAME_SEQUENCE[animation.step]].clone();
+    }
+}
+
+pub fn gravity_system(
+    mut query: Query<(&mut Player, Has<Diving>), Without<Bobbing>>,
+    physics: Res<Physics>,
+    direction: Res<GravityDirection>,
+    time: Res<Time>,
+) {
+    if let Ok((mut player, diving)) = query.get_single_mut() {
+        let dt = time.delta_seconds();
+        let (gravity, terminal_velocity) = if diving {
+            (
+                physics.gravity * DIVE_GRAVITY_MULTIPLIER,
+                physics.terminal_velocity.min(DIVE_TERMINAL_VELOCITY),
+            )
+        } else {
+            (physics.gravity, physics.terminal_velocity)
+        };
+        let mut y_vel = player.y_vel * direction.sign();
+        y_vel += gravity * dt;
+        y_vel *= (1.0 - physics.drag * dt).max(0.0);
+        y_vel = y_vel.max(-terminal_velocity);
+        player.y_vel = y_vel * direction.sign();
+    }
+}
+
+/// Makes the bird hover in place around where it was when bobbing started. Gravity and
+/// velocity don't move the bird while it is bobbing.
+#[derive(Component)]
+pub struct Bobbing {
+    origin: f32,
+    elapsed: f32,
+}
+
+fn start_bobbing(query: Query<(Entity, &Transform), With<Player>>, mut commands: Commands) {
+    for (entity, transform) in query.iter() {
+        commands.entity(entity).insert(Bobbing {
+            origin: transform.translation.y,
+            elapsed: 0.0,
+        });
+    }
+}
+
+/// Gameplay starts from wherever the bird is in its bob, so it doesn't jump on the first frame.
+fn stop_bobbing(query: Query<Entity, With<Bobbing>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).remove::<Bobbing>();
+    }
+}
+
+pub fn idle_bob_system(
+    mut query: Query<(&mut Transform, &mut Player, &mut Bobbing)>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut player, mut bobbing) in query.iter_mut() {
+        bobbing.elapsed += time.delta_seconds();
+        let phase = bobbing.elapsed * BOB_FREQUENCY * std::f32::consts::TAU;
+        transform.translation.y = bobbing.origin + BOB_AMPLITUDE * phase.sin();
+        // Don't carry a flap made while bobbing into gameplay
+        player.y_vel = 0.0;
+    }
+}
+
+/// Tapping on the menu fades into the game.
+fn start_game_system(
+    mut flap_event: EventReader<FlapEvent>,
+    transition: Option<Res<Transition>>,
+    accessibility: Res<Accessibility>,
+    mut commands: Commands,
+) {
+    if flap_event.iter().count() > 0 && transition.is_none() {
+        commands.insert_resource(Transition::new(
+            GameState::Menu,
+            GameState::Game,
+            &accessibility,
+        ));
+    }
+}
+
+fn start_velocity_system(mut query: Query<&mut Player>, physics: Res<Physics>) {
     if let Ok(mut player) = query.get_single_mut() {
-        player.y_vel += GRAVITY * time.delta_seconds();
+        player.y_vel = physics.start_velocity;
     }
 }
 
-fn move_system(mut query: Query<(&mut Transform, &Player)>, time: Res<Time>) {
+fn reset_run_timer(mut run_timer: ResMut<RunTimer>) {
+    run_timer.0.reset();
+}
+
+fn run_timer_system(mut run_timer: ResMut<RunTimer>, time: Res<Time>) {
+    run_timer.0.tick(time.delta());
+}
+
+pub fn move_system(mut query: Query<(&mut Transform, &Player), Without<Bobbing>>, time: Res<Time>) {
     if let Ok((mut player_transform, player)) = query.get_single_mut() {
-        player_transform.translation += Vec3::Y * player.y_vel * time.delta_seconds();
+        player_transform.translation +=
+            Vec3::new(player.x_vel, player.y_vel, 0.0) * time.delta_seconds();
+    }
+}
+
+pub fn steer_input_system(
+    mut query: Query<&mut Player>,
+    keyboard_input: Res<Input<KeyCode>>,
+    free_fly: Res<FreeFlyMode>,
+) {
+    if let Ok(mut player) = query.get_single_mut() {
+        if !free_fly.enabled {
+            player.x_vel = 0.0;
+            return;
+        }
+
+        let mut direction = 0.0;
+        if keyboard_input.any_pressed([KeyCode::A, KeyCode::Left]) {
+            direction -= 1.0;
+        }
+        if keyboard_input.any_pressed([KeyCode::D, KeyCode::Right]) {
+            direction += 1.0;
+        }
+        player.x_vel = direction * STEER_SPEED;
+    }
+}
+
+#[derive(Event)]
+pub struct FlapEvent {
+    /// Fraction of the normal jump velocity to flap with.
+    pub strength: f32,
+}
+
+impl Default for FlapEvent {
+    fn default() -> Self {
+        Self { strength: 1.0 }
     }
 }
 
-#[derive(Event, Default)]
-pub struct FlapEvent;
+/// Analog trigger value, after [`KeyBindings::trigger_value`], at which the trigger counts as pulled.
+pub const TRIGGER_PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    /// Mouse buttons that make the bird flap.
+    pub flap: Vec<MouseButton>,
+    /// Keys that make the bird dive while held, when [`DiveMode`] is enabled.
+    pub dive: Vec<KeyCode>,
+    /// Gamepad buttons that make the bird flap.
+    pub gamepad_flap: Vec<GamepadButtonType>,
+    /// Analog trigger that makes the bird flap when pulled.
+    pub gamepad_flap_trigger: GamepadButtonType,
+    /// Trigger values at or below this are ignored so a worn trigger can't flap on its own.
+    pub trigger_deadzone: f32,
+    /// Scales trigger travel past the deadzone. Higher values flap with a lighter pull.
+    pub trigger_sensitivity: f32,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            flap: vec![MouseButton::Left, MouseButton::Right],
+            dive: vec![KeyCode::S, KeyCode::Down],
+            gamepad_flap: vec![GamepadButtonType::South],
+            gamepad_flap_trigger: GamepadButtonType::RightTrigger2,
+            trigger_deadzone: 0.15,
+            trigger_sensitivity: 1.0,
+        }
+    }
+}
 
+impl KeyBindings {
+    /// Maps a raw trigger value to `0.0..=1.0` after applying the deadzone and sensitivity.
+    pub fn trigger_value(&self, raw: f32) -> f32 {
+        if raw <= self.trigger_deadzone {
+            return 0.0;
+        }
+
+        let travel =
+            (raw - self.trigger_deadzone) / (1.0 - self.trigger_deadzone).max(f32::EPSILON);
+        (travel * self.trigger_sensitivity).min(1.0)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn flap_input_system(
     query: Query<&Player>,
     mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadButton>>,
+    bindings: Res<KeyBindings>,
+    accessibility: Res<Accessibility>,
+    transition: Option<Res<Transition>>,
+    time: Res<Time>,
+    mut hover_timer: Local<Timer>,
+    mut trigger_was_pulled: Local<bool>,
     mut event_writer: EventWriter<FlapEvent>,
 ) {
-    // Player not in scene
-    if query.get_single().is_err() {
+    // Player not in scene, or the screen is fading between states
+    if query.get_single().is_err() || transition.is_some() {
         return;
     }
 
-    if mouse_input.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+    let gamepad_buttons = || {
+        gamepads.iter().flat_map(|gamepad| {
+            bindings
+                .gamepad_flap
+                .iter()
+                .map(move |&button_type| GamepadButton::new(gamepad, button_type))
+        })
+    };
+    let trigger_pulled = gamepads.iter().any(|gamepad| {
+        gamepad_axes
+            .get(GamepadButton::new(gamepad, bindings.gamepad_flap_trigger))
+            .is_some_and(|raw| bindings.trigger_value(raw) >= TRIGGER_PRESS_THRESHOLD)
+    });
+    let trigger_just_pulled = trigger_pulled && !*trigger_was_pulled;
+    *trigger_was_pulled = trigger_pulled;
+
+    let just_pressed = mouse_input.any_just_pressed(bindings.flap.iter().copied())
+        || gamepad_input.any_just_pressed(gamepad_buttons())
+        || trigger_just_pulled;
+    let held = mouse_input.any_pressed(bindings.flap.iter().copied())
+        || gamepad_input.any_pressed(gamepad_buttons())
+        || trigger_pulled;
+
+    if just_pressed {
         event_writer.send_default();
+        *hover_timer = Timer::from_seconds(HOVER_FLAP_INTERVAL, TimerMode::Repeating);
+    } else if accessibility.hold_to_hover && held && hover_timer.tick(time.delta()).just_finished()
+    {
+        event_writer.send(FlapEvent {
+            strength: HOVER_FLAP_STRENGTH,
+        });
+    }
+}
+
+pub fn dive_input_system(
+    query: Query<(Entity, Has<Diving>), With<Player>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    dive_mode: Res<DiveMode>,
+    mut commands: Commands,
+) {
+    if let Ok((entity, diving)) = query.get_single() {
+        let dive_held =
+            dive_mode.enabled && keyboard_input.any_pressed(bindings.dive.iter().copied());
+        if dive_held && !diving {
+            commands.entity(entity).insert(Diving);
+        } else if !dive_held && diving {
+            commands.entity(entity).remove::<Diving>();
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_flap_system(
     mut query: Query<&mut Player>,
     mut flap_event: EventReader<FlapEvent>,
+    physics: Res<Physics>,
+    soft_start: Res<SoftStart>,
+    run_timer: Res<RunTimer>,
+    state: Res<State<GameState>>,
+    direction: Res<GravityDirection>,
+    audio: Res<AudioAvailability>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
     if let Ok(mut player) = query.get_single_mut() {
-        if flap_event.iter().any(|_| true) {
-            player.y_vel = JUMP_VELOCITY;
-            commands.spawn(AudioBundle {
-                source: asset_server.load("audio/sfx_wing.ogg"),
-                settings: PlaybackSettings::DESPAWN,
-            });
+        // Several flaps in one frame count as one, but all of them must be read so the
+        // leftovers don't turn into another flap next frame.
+        let strength = flap_event.iter().map(|flap| flap.strength).reduce(f32::max);
+        if let Some(mut strength) = strength {
+            if soft_start.enabled && *state.get() == GameState::Game {
+                let t = (run_timer.0.elapsed_secs() / SOFT_START_WINDOW).min(1.0);
+                strength *= lerp(t, SOFT_START_STRENGTH, 1.0);
+            }
+            let sign = direction.sign();
+            player.y_vel = sign
+                * physics
+                    .flap_mode
+                    .apply(player.y_vel * sign, physics.jump_velocity * strength);
+            if audio.available {
+                // Vary the pitch a little so repeated flaps don't sound identical
+                let speed = 1.0
+                    + rand::thread_rng().gen_range(-FLAP_PITCH_VARIATION..=FLAP_PITCH_VARIATION);
+                commands.spawn(AudioBundle {
+                    source: asset_server.load("audio/sfx_wing.ogg"),
+                    settings: PlaybackSettings {
+                        speed,
+                        ..PlaybackSettings::DESPAWN
+                    },
+                });
+            }
         }
+    } else {
+        flap_event.clear();
     }
 }
 
-pub fn constrain_player_system(mut query: Query<(&mut Player, &mut Transform)>) {
+/// How [`constrain_player_system`] limits the bird's position.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub enum ConstrainMode {
+    /// No limits; the bird can leave the screen. Used on the menu, where the bird only bobs.
+    Free,
+    /// The bird rests on the ground and can only go slightly above the top of the play area.
+    /// Used while playing.
+    #[default]
+    PlayArea,
+    /// The bird is kept between two world heights. Not used by the game itself.
+    Band { min: f32, max: f32 },
+}
+
+fn set_constrain_mode(mode: ConstrainMode) -> impl FnMut(ResMut<ConstrainMode>) {
+    move |mut current| {
+        current.set_if_neq(mode);
+    }
+}
+
+pub fn constrain_player_system(
+    mut query: Query<(&mut Player, &mut Transform)>,
+    play_area: Res<PlayArea>,
+    mode: Res<ConstrainMode>,
+    direction: Res<GravityDirection>,
+    bird_size: Res<BirdSize>,
+) {
     if let Ok((mut player, mut transform)) = query.get_single_mut() {
-        if transform.translation.y < -PLAYER_SIZE.y && player.y_vel < 0.0 {
-            transform.translation.y = -PLAYER_SIZE.y;
-            player.y_vel = 0.0;
-        } else if transform.translation.y > BASE_RESOLUTION.y + PLAYER_SIZE.y && player.y_vel > 0.0
-        {
-            transform.translation.y = BASE_RESOLUTION.y + PLAYER_SIZE.y;
-            player.y_vel = 0.0;
-        }
+        let size = bird_size.size();
+        let (floor, ceiling) = match (*mode, *direction) {
+            (ConstrainMode::Free, _) => return,
+            // The bird rests on top of the ground
+            (ConstrainMode::PlayArea, GravityDirection::Down) => {
+                (play_area.bottom() + size.y / 2.0, play_area.top() + size.y)
+            }
+            // The bird rests against the top of the play area and still can't enter the ground
+            (ConstrainMode::PlayArea, GravityDirection::Up) => (
+                play_area.bottom() + size.y / 2.0,
+                play_area.top() - size.y / 2.0,
+            ),
+            (ConstrainMode::Band { min, max }, _) => (min, max),
+        };
+        constrain_axis(
+            &mut transform.translation.y,
+            &mut player.y_vel,
+            floor,
+            ceiling,
+        );
+
+        // Keep the whole bird on screen when it can steer
+        let left = size.x / 2.0;
+        let right = play_area.size.x - size.x / 2.0;
+        constrain_axis(&mut transform.translation.x, &mut player.x_vel, left, right);
     }
 }
 
-pub fn debug_on_press(query: Query<(&Transform, &Player)>, keyboard_input: Res<Input<KeyCode>>) {
+/// Stops `position` from moving further past `min` or `max`. Something resting exactly on a
+/// limit is stopped too, otherwise it would dip past it every other frame.
+fn constrain_axis(position: &mut f32, velocity: &mut f32, min: f32, max: f32) {
+    if *position <= min && *velocity < 0.0 {
+        *position = min;
+        *velocity = 0.0;
+    } else if *position >= max && *velocity > 0.0 {
+        *position = max;
+        *velocity = 0.0;
+    }
+}
+
+pub fn debug_on_press(
+    query: Query<(&Transform, &Player)>,
+    keyboard_input: Res<Input<KeyCode>>,
+    render_scale: Res<RenderScale>,
+    play_area: Res<PlayArea>,
+) {
     if let Ok((transform, player)) = query.get_single() {
         if keyboard_input.just_pressed(KeyCode::Space) {
-            println!("Player XYZ: {}, Y-Vel: {}", transform.translation, player.y_vel);
+            println!(
+                "Player XYZ: {}, Window XY: {}, Y-Vel: {}",
+                transform.translation,
+                world_to_window(transform.translation.truncate(), &render_scale, &play_area),
+                player.y_vel
+            );
         }
     }
 }
@@ -179,6 +1204,68 @@ fn lerp_window(uv: Vec2) -> Vec2 {
     lerp_2d(BASE_RESOLUTION, uv)
 }
 
+/// Loads `sprites/<name>`, unless the player has put their own image at `custom/<name>` in the
+/// assets folder. Custom sprites are not supported on wasm.
+pub fn load_sprite(asset_server: &AssetServer, name: &str) -> Handle<Image> {
+    asset_server.load(sprite_path(asset_server, name))
+}
+
+fn sprite_path(asset_server: &AssetServer, name: &str) -> String {
+    let custom = format!("custom/{}", name);
+    if custom_asset_exists(asset_server, &custom) {
+        custom
+    } else {
+        format!("sprites/{}", name)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn custom_asset_exists(asset_server: &AssetServer, path: &str) -> bool {
+    asset_server
+        .asset_io()
+        .get_metadata(std::path::Path::new(path))
+        .is_ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn custom_asset_exists(_asset_server: &AssetServer, _path: &str) -> bool {
+    false
+}
+
+/// Converts a world position to logical window pixels (origin top-left), as used by UI nodes
+/// and the cursor. Assumes the camera from [`spawn_camera`], kept up to date by [`zoom_system`].
+pub fn world_to_window(world: Vec2, render_scale: &RenderScale, play_area: &PlayArea) -> Vec2 {
+    let window_centre = BASE_RESOLUTION * SCREEN_SCALE / 2.0;
+    let from_camera =
+        (world - render_scale.camera_position(play_area)) / render_scale.projection_scale();
+    Vec2 {
+        x: window_centre.x + from_camera.x,
+        // Window y grows downwards
+        y: window_centre.y - from_camera.y,
+    }
+}
+
+/// The cursor's position in world space, or `None` if it is outside the window.
+pub fn cursor_world_position(
+    window: &Window,
+    render_scale: &RenderScale,
+    play_area: &PlayArea,
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .map(|cursor| window_to_world(cursor, render_scale, play_area))
+}
+
+/// Inverse of [`world_to_window`].
+pub fn window_to_world(window: Vec2, render_scale: &RenderScale, play_area: &PlayArea) -> Vec2 {
+    let window_centre = BASE_RESOLUTION * SCREEN_SCALE / 2.0;
+    let from_camera = Vec2 {
+        x: window.x - window_centre.x,
+        y: window_centre.y - window.y,
+    };
+    render_scale.camera_position(play_area) + from_camera * render_scale.projection_scale()
+}
+
 fn lerp_2d(vec: Vec2, uv: Vec2) -> Vec2 {
     Vec2 {
         x: lerp(uv.x, 0.0, vec.x),
@@ -189,3 +1276,558 @@ fn lerp_2d(vec: Vec2, uv: Vec2) -> Vec2 {
 fn lerp(t: f32, a: f32, b: f32) -> f32 {
     a * (1.0 - t) + b * t
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn jump_arc_rises_then_falls() {
+        let mut app = player_app();
+        start_game(&mut app);
+        let start = player_transform(&mut app).translation.y;
+
+        app.world.send_event(FlapEvent::default());
+        let dt = 1.0 / 60.0;
+        let mut peak = start;
+        for _ in 0..30 {
+            step(&mut app, dt);
+            peak = peak.max(player_transform(&mut app).translation.y);
+        }
+
+        // Rising at JUMP_VELOCITY against GRAVITY peaks at v^2 / 2g, less about half a frame's
+        // rise because gravity is also applied on the frame of the flap
+        let expected_peak =
+            JUMP_VELOCITY * JUMP_VELOCITY / (2.0 * -GRAVITY) - JUMP_VELOCITY * dt / 2.0;
+        assert!((peak - start - expected_peak).abs() < 0.1);
+        assert!(player(&mut app).y_vel < 0.0);
+        assert!(player_transform(&mut app).translation.y < peak);
+    }
+
+    #[test]
+    fn flap_while_falling_gives_upward_velocity() {
+        let mut app = player_app();
+        start_game(&mut app);
+        player(&mut app).y_vel = -200.0;
+
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 60.0);
+
+        // Gravity runs after the flap in the same frame but must not cancel it
+        assert!(player(&mut app).y_vel > 0.0);
+    }
+
+    #[test]
+    fn no_drag_matches_plain_gravity() {
+        let mut app = player_app();
+        start_game(&mut app);
+        app.world.resource_mut::<Physics>().drag = 0.0;
+        player(&mut app).y_vel = 50.0;
+
+        // Exactly representable so the clock doesn't round it
+        let dt = 1.0 / 64.0;
+        step(&mut app, dt);
+
+        assert_eq!(player(&mut app).y_vel, 50.0 + GRAVITY * dt);
+    }
+
+    #[test]
+    fn start_velocity_is_applied_once_on_entering_game() {
+        let mut app = player_app();
+        {
+            let mut physics = app.world.resource_mut::<Physics>();
+            physics.start_velocity = 100.0;
+            // Keep the velocity still so only the start velocity can change it
+            physics.gravity = 0.0;
+        }
+
+        start_game(&mut app);
+        assert_eq!(player(&mut app).y_vel, 100.0);
+
+        player(&mut app).y_vel = 0.0;
+        for _ in 0..5 {
+            step(&mut app, 1.0 / 60.0);
+        }
+        assert_eq!(player(&mut app).y_vel, 0.0);
+    }
+
+    #[test]
+    fn set_velocity_flap_cancels_a_fall() {
+        assert_eq!(
+            FlapMode::SetVelocity.apply(-200.0, JUMP_VELOCITY),
+            JUMP_VELOCITY
+        );
+    }
+
+    #[test]
+    fn impulse_flap_keeps_momentum_from_a_fall() {
+        assert_eq!(
+            FlapMode::AddImpulse.apply(-200.0, JUMP_VELOCITY),
+            JUMP_VELOCITY - 200.0
+        );
+        // Rising flaps are still capped at the jump velocity
+        assert_eq!(
+            FlapMode::AddImpulse.apply(100.0, JUMP_VELOCITY),
+            JUMP_VELOCITY
+        );
+    }
+
+    // Custom sprites are skipped on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn custom_sprite_is_preferred_over_default() {
+        let assets = std::env::temp_dir().join(format!("flappy-assets-{}", std::process::id()));
+        for dir in ["sprites", "custom"] {
+            std::fs::create_dir_all(assets.join(dir)).unwrap();
+        }
+        std::fs::write(assets.join("sprites/bird-0.png"), []).unwrap();
+        std::fs::write(assets.join("custom/bird-0.png"), []).unwrap();
+        let asset_server = AssetServer::new(bevy::asset::FileAssetIo::new(&assets, &None));
+
+        let bird = sprite_path(&asset_server, "bird-0.png");
+        let ground = sprite_path(&asset_server, "ground.png");
+        std::fs::remove_dir_all(&assets).unwrap();
+
+        assert_eq!(bird, "custom/bird-0.png");
+        // Sprites without a custom file still use the default
+        assert_eq!(ground, "sprites/ground.png");
+    }
+
+    /// Counts how many animation frames the bird advances by over `steps` frames of `dt` seconds.
+    fn animation_frames_advanced(dt: f32, steps: usize) -> usize {
+        let mut app = player_app();
+        let mut query = app.world.query::<&BirdAnimation>();
+        let mut advanced = 0;
+        for _ in 0..steps {
+            let before = query.single(&app.world).step;
+            step(&mut app, dt);
+            let after = query.single(&app.world).step;
+            advanced += (after + BIRD_FRAME_SEQUENCE.len() - before) % BIRD_FRAME_SEQUENCE.len();
+        }
+        advanced
+    }
+
+    #[test]
+    fn animation_speed_does_not_depend_on_frame_rate() {
+        // Both are 1.0625 seconds, chosen so neither lands on a frame boundary
+        let fast = animation_frames_advanced(1.0 / 64.0, 68);
+        let slow = animation_frames_advanced(1.0 / 16.0, 17);
+
+        assert_eq!(fast, slow);
+        assert_eq!(fast, (1.0625 / BIRD_FRAME_TIME) as usize);
+    }
+
+    #[test]
+    fn bird_rests_on_top_edge_of_ground() {
+        let mut app = player_app();
+        app.add_systems(Update, spawn_ground.run_if(run_once()));
+        start_game(&mut app);
+
+        let ground_top = app
+            .world
+            .query_filtered::<&Transform, (With<Handle<Image>>, Without<Player>)>()
+            .single(&app.world)
+            .translation
+            .y
+            + GROUND_SIZE.y / 2.0;
+        assert_eq!(ground_top, PlayArea::default().bottom());
+
+        // Long enough to fall from the middle of the screen, then check every frame it rests
+        for frame in 0..120 {
+            step(&mut app, 1.0 / 60.0);
+            if frame >= 60 {
+                let bird_bottom = player_transform(&mut app).translation.y - PLAYER_SIZE.y / 2.0;
+                assert_eq!(bird_bottom, ground_top);
+            }
+        }
+    }
+
+    #[test]
+    fn classic_fall_is_not_capped() {
+        let mut app = player_app();
+        start_game(&mut app);
+        player(&mut app).y_vel = -390.0;
+
+        step(&mut app, 1.0 / 16.0);
+        assert_eq!(player(&mut app).y_vel, -390.0 + GRAVITY / 16.0);
+    }
+
+    #[test]
+    fn modern_fall_is_capped() {
+        let mut app = player_app();
+        app.insert_resource(PhysicsPreset::Modern);
+        start_game(&mut app);
+        player(&mut app).y_vel = -240.0;
+
+        step(&mut app, 1.0 / 16.0);
+        assert_eq!(
+            player(&mut app).y_vel,
+            -PhysicsPreset::Modern.physics().terminal_velocity
+        );
+    }
+
+    #[test]
+    fn constrain_mode_follows_game_state() {
+        let mut app = player_app();
+        assert_eq!(*app.world.resource::<ConstrainMode>(), ConstrainMode::Free);
+
+        start_game(&mut app);
+        assert_eq!(
+            *app.world.resource::<ConstrainMode>(),
+            ConstrainMode::PlayArea
+        );
+    }
+
+    /// Puts the bird at `y` moving at `y_vel` under `mode`, then runs one frame.
+    fn constrained_y(mode: ConstrainMode, y: f32, y_vel: f32) -> f32 {
+        let mut app = player_app();
+        start_game(&mut app);
+        app.insert_resource(mode);
+        player_transform(&mut app).translation.y = y;
+        player(&mut app).y_vel = y_vel;
+
+        step(&mut app, 1.0 / 16.0);
+        player_transform(&mut app).translation.y
+    }
+
+    #[test]
+    fn free_mode_lets_the_bird_leave_the_screen() {
+        let below_ground = PlayArea::default().bottom() - 50.0;
+        assert!(constrained_y(ConstrainMode::Free, below_ground, -100.0) < below_ground);
+    }
+
+    #[test]
+    fn play_area_mode_stops_the_bird_at_the_floor_and_ceiling() {
+        let play_area = PlayArea::default();
+        let floor = play_area.bottom() + PLAYER_SIZE.y / 2.0;
+        let ceiling = play_area.top() + PLAYER_SIZE.y;
+        assert_eq!(
+            constrained_y(ConstrainMode::PlayArea, floor - 5.0, -100.0),
+            floor
+        );
+        assert_eq!(
+            constrained_y(ConstrainMode::PlayArea, ceiling + 5.0, 100.0),
+            ceiling
+        );
+    }
+
+    #[test]
+    fn band_mode_keeps_the_bird_between_its_heights() {
+        let band = ConstrainMode::Band {
+            min: 50.0,
+            max: 60.0,
+        };
+        assert_eq!(constrained_y(band, 40.0, -10.0), 50.0);
+        assert_eq!(constrained_y(band, 70.0, 100.0), 60.0);
+    }
+
+    #[test]
+    fn frame_time_is_capped_after_a_stall() {
+        let speed = frame_time_speed(Duration::from_secs(30));
+        assert_eq!(Duration::from_secs(30).mul_f64(speed), MAX_FRAME_TIME);
+        assert_eq!(frame_time_speed(Duration::from_secs_f32(1.0 / 60.0)), 1.0);
+    }
+
+    #[test]
+    fn stalled_frame_only_advances_game_time_by_the_cap() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_systems(First, cap_frame_time_system.before(TimeSystem));
+        app.update();
+        // Pretend the last frame started half a minute ago
+        let stall = Duration::from_secs(30);
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(Instant::now() - stall);
+
+        app.update();
+        let time = app.world.resource::<Time>();
+        assert!(time.raw_delta() >= stall);
+        assert!(time.delta() <= MAX_FRAME_TIME);
+        assert!(time.delta() > MAX_FRAME_TIME.mul_f32(0.99));
+
+        // The next normal frame runs at full speed again
+        app.update();
+        let time = app.world.resource::<Time>();
+        assert_eq!(time.delta(), time.raw_delta());
+    }
+
+    #[test]
+    fn game_starts_from_the_current_bob_position() {
+        let mut app = player_app();
+        let origin = player_transform(&mut app).translation.y;
+        for _ in 0..3 {
+            step(&mut app, 1.0 / 16.0);
+        }
+        let bobbed = player_transform(&mut app).translation.y;
+        assert_ne!(bobbed, origin);
+
+        start_game(&mut app);
+        assert_eq!(player_transform(&mut app).translation.y, bobbed);
+    }
+
+    /// Fall speed after `frames` sixteenths of a second with nothing to land on, optionally
+    /// holding the dive key the whole time.
+    fn fall_speed_after(frames: usize, dive_held: bool) -> f32 {
+        let mut app = player_app();
+        app.insert_resource(DiveMode { enabled: true });
+        start_game(&mut app);
+        app.insert_resource(ConstrainMode::Free);
+        if dive_held {
+            app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::S);
+        }
+        for _ in 0..frames {
+            step(&mut app, 1.0 / 16.0);
+        }
+        -player(&mut app).y_vel
+    }
+
+    #[test]
+    fn diving_falls_faster() {
+        let normal = fall_speed_after(2, false);
+        let diving = fall_speed_after(2, true);
+        assert!(normal > 0.0);
+        assert!((diving - normal * DIVE_GRAVITY_MULTIPLIER).abs() < 1e-3);
+
+        // Classic falls are uncapped, but a dive still has a top speed
+        assert!(fall_speed_after(16, false) > DIVE_TERMINAL_VELOCITY);
+        assert_eq!(fall_speed_after(16, true), DIVE_TERMINAL_VELOCITY);
+    }
+
+    #[test]
+    fn sprite_offset_moves_the_art_but_not_the_hitbox() {
+        let offset = Vec2::new(4.0, -3.0);
+        // Like a custom sprite drawn at twice the size of the default one
+        let image_size = PLAYER_SIZE * 2.0;
+        let resting_y = |sprite_offset: Vec2| {
+            let mut app = player_app();
+            app.insert_resource(BirdSpriteOffset {
+                offset: sprite_offset,
+            });
+            let frames = app
+                .world
+                .query::<&BirdAnimation>()
+                .single(&app.world)
+                .frames
+                .clone();
+            let mut images = app.world.resource_mut::<Assets<Image>>();
+            for frame in frames {
+                images.set_untracked(
+                    frame,
+                    Image::new_fill(
+                        Extent3d {
+                            width: image_size.x as u32,
+                            height: image_size.y as u32,
+                            depth_or_array_layers: 1,
+                        },
+                        TextureDimension::D2,
+                        &[255; 4],
+                        TextureFormat::Rgba8UnormSrgb,
+                    ),
+                );
+            }
+            let y = settle(&mut app);
+            let anchor = app
+                .world
+                .query_filtered::<&Sprite, With<Player>>()
+                .single(&app.world)
+                .anchor
+                .as_vec();
+            assert_eq!(anchor * image_size, -sprite_offset);
+            y
+        };
+
+        assert_eq!(resting_y(offset), resting_y(Vec2::ZERO));
+    }
+
+    /// Velocity right after flapping once `frames_before` frames into a run with [`SoftStart`].
+    fn soft_start_flap_velocity(frames_before: usize) -> f32 {
+        let mut app = player_app();
+        app.insert_resource(SoftStart { enabled: true });
+        start_game(&mut app);
+        for _ in 0..frames_before {
+            step(&mut app, 1.0 / 16.0);
+        }
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 16.0);
+        player(&mut app).y_vel
+    }
+
+    #[test]
+    fn soft_start_flap_is_weaker_than_a_later_one() {
+        let early = soft_start_flap_velocity(0);
+        let late = soft_start_flap_velocity((SOFT_START_WINDOW * 16.0) as usize + 4);
+        assert!(early > 0.0);
+        assert!(early < late);
+        assert_eq!(late, JUMP_VELOCITY + GRAVITY / 16.0);
+    }
+
+    #[test]
+    fn window_coordinates_round_trip() {
+        let play_area = PlayArea::default();
+        let points = [Vec2::ZERO, BASE_RESOLUTION, Vec2::new(12.5, 200.25)];
+        for zoom in [1.0, MIN_ZOOM, MAX_ZOOM] {
+            for camera_offset_y in [0.0, 16.0, -16.0] {
+                let render_scale = RenderScale {
+                    zoom,
+                    camera_offset_y,
+                };
+                for world in points {
+                    let window = world_to_window(world, &render_scale, &play_area);
+                    let back = window_to_world(window, &render_scale, &play_area);
+                    assert!(
+                        (back - world).length() < 1e-3,
+                        "{} came back as {} with {:?}",
+                        world,
+                        back,
+                        render_scale
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn camera_centre_is_window_centre() {
+        let play_area = PlayArea::default();
+        let render_scale = RenderScale {
+            zoom: MAX_ZOOM,
+            camera_offset_y: 16.0,
+        };
+        assert_eq!(
+            world_to_window(
+                render_scale.camera_position(&play_area),
+                &render_scale,
+                &play_area
+            ),
+            BASE_RESOLUTION * SCREEN_SCALE / 2.0
+        );
+        // At the default zoom the top-left of the base resolution is the window's origin
+        assert_eq!(
+            world_to_window(
+                Vec2::new(0.0, BASE_RESOLUTION.y),
+                &RenderScale::default(),
+                &play_area
+            ),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn bigger_bird_rests_higher_on_the_ground() {
+        let resting_y = |scale: f32| {
+            let mut app = player_app();
+            app.insert_resource(BirdSize { scale });
+            let y = settle(&mut app);
+            assert_eq!(
+                player_transform(&mut app).scale.truncate(),
+                Vec2::splat(scale)
+            );
+            y
+        };
+
+        // Doubling the size adds half a bird to the distance from the centre to the floor
+        assert_eq!(resting_y(2.0) - resting_y(1.0), PLAYER_SIZE.y / 2.0);
+    }
+
+    /// Number of sound entities left after one flap.
+    fn sounds_after_flap(available: bool) -> usize {
+        let mut app = player_app();
+        app.insert_resource(AudioAvailability { available });
+        start_game(&mut app);
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 60.0);
+        app.world
+            .query::<&Handle<AudioSource>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn flap_is_silent_without_audio() {
+        assert_eq!(sounds_after_flap(false), 0);
+        assert_eq!(sounds_after_flap(true), 1);
+    }
+
+    #[test]
+    fn still_sprite_does_not_drift_when_the_camera_moves() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin))
+            .init_resource::<DisplaySettings>()
+            .init_resource::<RenderScale>()
+            .init_resource::<PlayArea>()
+            .add_systems(
+                PostUpdate,
+                (
+                    unsnap_system.before(TransformSystem::TransformPropagate),
+                    pixel_snap_system.after(TransformSystem::TransformPropagate),
+                ),
+            );
+        let position = Vec2::new(40.3, 100.3);
+        let sprite = app
+            .world
+            .spawn((
+                Sprite::default(),
+                TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            ))
+            .id();
+
+        let pixel = RenderScale::default().projection_scale();
+        for tenths in 0..=160 {
+            app.world.resource_mut::<RenderScale>().camera_offset_y = tenths as f32 / 10.0;
+            app.update();
+            let drawn = app
+                .world
+                .get::<GlobalTransform>(sprite)
+                .unwrap()
+                .translation()
+                .truncate();
+            assert!(
+                (drawn - position).abs().max_element() <= pixel / 2.0 + 1e-4,
+                "drawn at {} instead of {}",
+                drawn,
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn camera_offset_keeps_the_play_area_edges_on_screen() {
+        let play_area = PlayArea::default();
+        for zoom in [1.0, 1.5, MAX_ZOOM] {
+            for camera_offset_y in [-1000.0, -16.0, 0.0, 16.0, 1000.0] {
+                let render_scale = RenderScale {
+                    zoom,
+                    camera_offset_y,
+                };
+                let centre = render_scale.camera_position(&play_area).y;
+                let half_height = render_scale.view_half_height();
+                assert!(
+                    centre - half_height <= play_area.bottom() + 1e-3,
+                    "floor hidden with {:?}",
+                    render_scale
+                );
+                assert!(
+                    centre + half_height >= play_area.top() - 1e-3,
+                    "top hidden with {:?}",
+                    render_scale
+                );
+            }
+        }
+
+        // Zoomed in too far to show both, the camera splits the difference
+        let zoomed_in = RenderScale {
+            zoom: MIN_ZOOM,
+            camera_offset_y: 1000.0,
+        };
+        let (min, max) = zoomed_in.camera_offset_range(&play_area);
+        assert_eq!(min, max);
+        assert_eq!(
+            zoomed_in.camera_position(&play_area).y,
+            (play_area.bottom() + play_area.top()) / 2.0
+        );
+    }
+}
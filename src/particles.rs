@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    performance::PerformanceMode, z_layer, Accessibility, BirdSize, FlapEvent, Player,
+    BASE_RESOLUTION,
+};
+
+pub const AMBIENT_PARTICLE_COUNT: usize = 12;
+/// How many of the pooled particles are shown in [`PerformanceMode::Low`].
+pub const LOW_PERFORMANCE_AMBIENT_PARTICLE_COUNT: usize = 4;
+pub const AMBIENT_PARTICLE_MIN_SPEED: f32 = 4.0;
+pub const AMBIENT_PARTICLE_MAX_SPEED: f32 = 12.0;
+/// How far past the edge of the screen a particle goes before wrapping around.
+const AMBIENT_PARTICLE_MARGIN: f32 = 4.0;
+
+/// Puffs spawned behind the bird on each flap.
+pub const PUFF_PARTICLE_COUNT: usize = 3;
+pub const LOW_PERFORMANCE_PUFF_PARTICLE_COUNT: usize = 1;
+/// Seconds a puff takes to fade out.
+pub const PUFF_PARTICLE_LIFETIME: f32 = 0.3;
+const PUFF_PARTICLE_SPEED: f32 = 20.0;
+
+/// A slow-drifting speck in the background. Faster particles are drawn bigger and brighter
+/// so they read as closer to the camera.
+#[derive(Component)]
+pub struct AmbientParticle {
+    speed: f32,
+    /// Position in the pool.
+    index: usize,
+}
+
+/// Short-lived puff of air left behind by a flap.
+#[derive(Component)]
+pub struct PuffParticle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_ambient_particles)
+            .add_systems(Update, ambient_particle_system)
+            .add_systems(Update, (spawn_puff_system, puff_particle_system));
+    }
+}
+
+/// Spawns the whole pool up front; particles are recycled by wrapping them around the screen.
+fn spawn_ambient_particles(mut commands: Commands) {
+    let mut rng = rand::thread_rng();
+
+    for index in 0..AMBIENT_PARTICLE_COUNT {
+        let speed = rng.gen_range(AMBIENT_PARTICLE_MIN_SPEED..AMBIENT_PARTICLE_MAX_SPEED);
+        let depth = (speed - AMBIENT_PARTICLE_MIN_SPEED)
+            / (AMBIENT_PARTICLE_MAX_SPEED - AMBIENT_PARTICLE_MIN_SPEED);
+        let x = rng.gen_range(0.0..BASE_RESOLUTION.x);
+        let y = rng.gen_range(0.0..BASE_RESOLUTION.y);
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.2 + 0.3 * depth),
+                    custom_size: Some(Vec2::splat(1.0 + depth)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, z_layer::AMBIENT_PARTICLES + 0.1 * depth),
+                ..default()
+            },
+            AmbientParticle { speed, index },
+        ));
+    }
+}
+
+fn ambient_particle_system(
+    mut query: Query<(&mut Transform, &mut Visibility, &AmbientParticle)>,
+    accessibility: Res<Accessibility>,
+    performance: Res<PerformanceMode>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+    let count = match *performance {
+        PerformanceMode::Low => LOW_PERFORMANCE_AMBIENT_PARTICLE_COUNT,
+        PerformanceMode::High => AMBIENT_PARTICLE_COUNT,
+    };
+
+    for (mut transform, mut visibility, particle) in query.iter_mut() {
+        if accessibility.reduced_motion || particle.index >= count {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+
+        transform.translation.x -= particle.speed * time.delta_seconds();
+        if transform.translation.x < -AMBIENT_PARTICLE_MARGIN {
+            transform.translation.x += BASE_RESOLUTION.x + 2.0 * AMBIENT_PARTICLE_MARGIN;
+            transform.translation.y = rng.gen_range(0.0..BASE_RESOLUTION.y);
+        }
+    }
+}
+
+fn spawn_puff_system(
+    mut commands: Commands,
+    mut flap_event: EventReader<FlapEvent>,
+    query: Query<&Transform, With<Player>>,
+    accessibility: Res<Accessibility>,
+    performance: Res<PerformanceMode>,
+    bird_size: Res<BirdSize>,
+) {
+    if flap_event.iter().count() == 0 || accessibility.reduced_motion {
+        return;
+    }
+
+    if let Ok(transform) = query.get_single() {
+        let mut rng = rand::thread_rng();
+        let count = match *performance {
+            PerformanceMode::Low => LOW_PERFORMANCE_PUFF_PARTICLE_COUNT,
+            PerformanceMode::High => PUFF_PARTICLE_COUNT,
+        };
+        let tail = transform.translation.truncate() - Vec2::new(bird_size.size().x / 2.0, 0.0);
+
+        for _ in 0..count {
+            // Somewhere between straight back and straight down
+            let velocity = Vec2::from_angle(rng.gen_range(PI..1.5 * PI)) * PUFF_PARTICLE_SPEED;
+
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+                        custom_size: Some(Vec2::splat(2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(tail.x, tail.y, z_layer::PUFF_PARTICLES),
+                    ..default()
+                },
+                PuffParticle {
+                    velocity,
+                    lifetime: Timer::from_seconds(PUFF_PARTICLE_LIFETIME, TimerMode::Once),
+                },
+            ));
+        }
+    }
+}
+
+fn puff_particle_system(
+    mut query: Query<(Entity, &mut Transform, &mut Sprite, &mut PuffParticle)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut sprite, mut puff) in query.iter_mut() {
+        if puff.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (puff.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(0.6 * puff.lifetime.percent_left());
+    }
+}
@@ -0,0 +1,213 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hud::HudSettings;
+
+/// How long the frame time is measured for at startup before picking a [`PerformanceMode`].
+pub const PERFORMANCE_SAMPLE_TIME: f32 = 3.0;
+/// Average frame time above which [`PerformanceMode::Low`] is picked automatically.
+pub const LOW_PERFORMANCE_FRAME_TIME: f32 = 1.0 / 45.0;
+
+/// The frame rate has to stay below this for [`PERF_WARNING_WINDOW`] seconds to show the warning.
+pub const PERF_WARNING_FPS: f64 = 30.0;
+pub const PERF_WARNING_WINDOW: f32 = 5.0;
+pub const PERF_WARNING_LOW_KEY: KeyCode = KeyCode::L;
+pub const PERF_WARNING_DISMISS_KEY: KeyCode = KeyCode::Escape;
+const TOAST_FONT_SIZE: f32 = 16.0;
+/// Distance from the bottom left corner of the window.
+const TOAST_MARGIN: f32 = 4.0;
+
+/// Effect systems scale down or switch off in [`PerformanceMode::Low`].
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PerformanceMode {
+    Low,
+    #[default]
+    High,
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceSettings {
+    /// The player's choice. `None` picks a mode from the measured frame time.
+    pub mode_override: Option<PerformanceMode>,
+}
+
+/// Toast suggesting [`PerformanceMode::Low`] after a sustained low frame rate.
+#[derive(Component)]
+pub struct PerfWarningToast;
+
+pub struct PerformancePlugin;
+
+impl Plugin for PerformancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PerformanceMode>()
+            .init_resource::<PerformanceSettings>()
+            .add_systems(First, performance_mode_system)
+            .add_systems(
+                Update,
+                (
+                    perf_warning_system,
+                    perf_warning_input_system,
+                    perf_warning_layout_system,
+                ),
+            );
+    }
+}
+
+#[derive(Default)]
+struct FrameTimeSample {
+    elapsed: f32,
+    frames: u32,
+    detected: Option<PerformanceMode>,
+}
+
+fn performance_mode_system(
+    settings: Res<PerformanceSettings>,
+    mut mode: ResMut<PerformanceMode>,
+    time: Res<Time>,
+    mut sample: Local<FrameTimeSample>,
+) {
+    if sample.detected.is_none() {
+        sample.elapsed += time.raw_delta_seconds();
+        sample.frames += 1;
+
+        if sample.elapsed >= PERFORMANCE_SAMPLE_TIME {
+            let average_frame_time = sample.elapsed / sample.frames as f32;
+            let detected = if average_frame_time > LOW_PERFORMANCE_FRAME_TIME {
+                PerformanceMode::Low
+            } else {
+                PerformanceMode::High
+            };
+            println!(
+                "Average frame time {:.1} ms, using {:?} performance mode",
+                average_frame_time * 1000.0,
+                detected
+            );
+            sample.detected = Some(detected);
+        }
+    }
+
+    if let Some(new_mode) = settings.mode_override.or(sample.detected) {
+        mode.set_if_neq(new_mode);
+    }
+}
+
+#[derive(Default)]
+struct SlowFrames {
+    elapsed: f32,
+    warned: bool,
+}
+
+/// Shows the warning at most once per run.
+fn perf_warning_system(
+    mut commands: Commands,
+    mode: Res<PerformanceMode>,
+    diagnostics: Res<DiagnosticsStore>,
+    hud_settings: Res<HudSettings>,
+    time: Res<Time>,
+    mut slow: Local<SlowFrames>,
+) {
+    // Nothing left to suggest once the mode is already low
+    if slow.warned || *mode == PerformanceMode::Low {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+    match fps {
+        Some(fps) if fps < PERF_WARNING_FPS => slow.elapsed += time.raw_delta_seconds(),
+        _ => slow.elapsed = 0.0,
+    }
+
+    if slow.elapsed >= PERF_WARNING_WINDOW {
+        slow.warned = true;
+        commands.spawn(perf_warning_toast(hud_settings.scale()));
+    }
+}
+
+fn perf_warning_toast(scale: f32) -> (TextBundle, PerfWarningToast) {
+    (
+        TextBundle::from_section(
+            format!(
+                "Low frame rate. Press {:?} for low quality or {:?} to dismiss",
+                PERF_WARNING_LOW_KEY, PERF_WARNING_DISMISS_KEY
+            ),
+            TextStyle {
+                font_size: TOAST_FONT_SIZE * scale,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(TOAST_MARGIN * scale),
+            left: Val::Px(TOAST_MARGIN * scale),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+        PerfWarningToast,
+    )
+}
+
+/// Keeps a toast that is already showing in step with [`HudSettings::hud_scale`].
+fn perf_warning_layout_system(
+    mut query: Query<(&mut Text, &mut Style), With<PerfWarningToast>>,
+    hud_settings: Res<HudSettings>,
+) {
+    if !hud_settings.is_changed() {
+        return;
+    }
+
+    let scale = hud_settings.scale();
+    for (mut text, mut style) in query.iter_mut() {
+        text.sections[0].style.font_size = TOAST_FONT_SIZE * scale;
+        style.bottom = Val::Px(TOAST_MARGIN * scale);
+        style.left = Val::Px(TOAST_MARGIN * scale);
+    }
+}
+
+fn perf_warning_input_system(
+    query: Query<Entity, With<PerfWarningToast>>,
+    mut commands: Commands,
+    mut settings: ResMut<PerformanceSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if let Ok(entity) = query.get_single() {
+        if keyboard_input.just_pressed(PERF_WARNING_LOW_KEY) {
+            settings.mode_override = Some(PerformanceMode::Low);
+            commands.entity(entity).despawn();
+        } else if keyboard_input.just_pressed(PERF_WARNING_DISMISS_KEY) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toast_follows_hud_scale() {
+        let mut app = App::new();
+        app.init_resource::<HudSettings>()
+            .add_systems(Update, perf_warning_layout_system);
+        let toast = app.world.spawn(perf_warning_toast(1.0)).id();
+        app.update();
+
+        app.world.resource_mut::<HudSettings>().hud_scale = 2.0;
+        app.update();
+        let toast = app.world.entity(toast);
+        assert_eq!(
+            toast.get::<Text>().unwrap().sections[0].style.font_size,
+            TOAST_FONT_SIZE * 2.0
+        );
+        assert_eq!(
+            toast.get::<Style>().unwrap().bottom,
+            Val::Px(TOAST_MARGIN * 2.0)
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Saves the player's settings to a file in their config directory and loads it at startup. There
+//! is no file system on wasm, so settings there only last for the session.
+
+use std::{error::Error, fmt, io};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fps::FpsCounterSettings, hud::HudSettings, performance::PerformanceSettings, DisplaySettings,
+    KeyBindings, PhysicsPreset, RenderScale,
+};
+
+/// Layout of the settings file written by this build. New fields only need a default, but renaming
+/// or reshaping one needs a new version and a step in [`migrate`].
+const SETTINGS_VERSION: u32 = 2;
+
+/// Everything that is saved. Fields missing from the file, e.g. ones added since it was written,
+/// keep their defaults.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    /// Files written before the version was saved count as version 1.
+    #[serde(default = "unversioned")]
+    version: u32,
+    display: DisplaySettings,
+    fps_counter: FpsCounterSettings,
+    bindings: KeyBindings,
+    physics_preset: PhysicsPreset,
+    render_scale: RenderScale,
+    hud: HudSettings,
+    performance: PerformanceSettings,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            display: default(),
+            fps_counter: default(),
+            bindings: default(),
+            physics_preset: default(),
+            render_scale: default(),
+            hud: default(),
+            performance: default(),
+        }
+    }
+}
+
+fn unversioned() -> u32 {
+    1
+}
+
+/// Upgrades a file written by an older build, one version at a time.
+fn migrate(mut file: SettingsFile) -> SettingsFile {
+    if file.version == 1 {
+        // Version 1 only lacks the version field, and its other missing fields have defaults
+        file.version = 2;
+    }
+    file
+}
+
+impl SettingsFile {
+    fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("settings are always serializable")
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// Nothing has been saved yet.
+    Missing,
+    /// There is nowhere to keep the file, e.g. `HOME` isn't set.
+    NoConfigDir,
+    Io(io::Error),
+    Corrupt(ron::error::SpannedError),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Missing => write!(f, "no settings have been saved"),
+            SettingsError::NoConfigDir => write!(f, "could not find a config directory"),
+            SettingsError::Io(e) => write!(f, "{}", e),
+            SettingsError::Corrupt(e) => write!(f, "settings file is corrupt: {}", e),
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SettingsError::Io(e) => Some(e),
+            SettingsError::Corrupt(e) => Some(e),
+            SettingsError::Missing | SettingsError::NoConfigDir => None,
+        }
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::NotFound {
+            SettingsError::Missing
+        } else {
+            SettingsError::Io(e)
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for SettingsError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SettingsError::Corrupt(e)
+    }
+}
+
+/// The contents of the settings file as last read or written, so unchanged settings aren't saved
+/// again.
+#[derive(Resource)]
+struct SavedSettings(String);
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let file = match load_settings() {
+            Ok(file) => file,
+            Err(SettingsError::Missing) => SettingsFile::default(),
+            Err(e) => {
+                println!("Using default settings: {}", e);
+                SettingsFile::default()
+            }
+        };
+        app.insert_resource(SavedSettings(file.to_ron()))
+            .insert_resource(file.display)
+            .insert_resource(file.fps_counter)
+            .insert_resource(file.bindings)
+            .insert_resource(file.physics_preset)
+            .insert_resource(file.render_scale)
+            .insert_resource(file.hud)
+            .insert_resource(file.performance)
+            .add_systems(Last, save_settings_system);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_settings_system(
+    display: Res<DisplaySettings>,
+    fps_counter: Res<FpsCounterSettings>,
+    bindings: Res<KeyBindings>,
+    physics_preset: Res<PhysicsPreset>,
+    render_scale: Res<RenderScale>,
+    hud: Res<HudSettings>,
+    performance: Res<PerformanceSettings>,
+    mut saved: ResMut<SavedSettings>,
+) {
+    if !display.is_changed()
+        && !fps_counter.is_changed()
+        && !bindings.is_changed()
+        && !physics_preset.is_changed()
+        && !render_scale.is_changed()
+        && !hud.is_changed()
+        && !performance.is_changed()
+    {
+        return;
+    }
+
+    let contents = SettingsFile {
+        version: SETTINGS_VERSION,
+        display: *display,
+        fps_counter: *fps_counter,
+        bindings: bindings.clone(),
+        physics_preset: *physics_preset,
+        render_scale: *render_scale,
+        hud: *hud,
+        performance: *performance,
+    }
+    .to_ron();
+    if contents != saved.0 {
+        if let Err(e) = write_settings(&contents) {
+            println!("Could not save settings: {}", e);
+        }
+        saved.0 = contents;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let config_dir = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    config_dir.map(|dir| dir.join("bevy-flappy-bird").join("settings.ron"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_settings() -> Result<SettingsFile, SettingsError> {
+    load_settings_from(&settings_path().ok_or(SettingsError::NoConfigDir)?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_settings_from(path: &std::path::Path) -> Result<SettingsFile, SettingsError> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(migrate(ron::from_str(&contents)?))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings(contents: &str) -> Result<(), SettingsError> {
+    write_settings_to(
+        &settings_path().ok_or(SettingsError::NoConfigDir)?,
+        contents,
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings_to(path: &std::path::Path, contents: &str) -> Result<(), SettingsError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(SettingsError::Io)?;
+    }
+    // Not through `From`, which would report a missing directory as nothing being saved yet
+    std::fs::write(path, contents).map_err(SettingsError::Io)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_settings() -> Result<SettingsFile, SettingsError> {
+    Err(SettingsError::Missing)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings(_contents: &str) -> Result<(), SettingsError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::PerformanceMode;
+
+    #[test]
+    fn settings_round_trip() {
+        let file = SettingsFile {
+            version: SETTINGS_VERSION,
+            display: DisplaySettings {
+                smooth_filtering: true,
+                ..default()
+            },
+            fps_counter: FpsCounterSettings { visible: true },
+            bindings: KeyBindings {
+                flap: vec![MouseButton::Left],
+                ..default()
+            },
+            physics_preset: PhysicsPreset::Modern,
+            render_scale: RenderScale {
+                camera_offset_y: 8.0,
+                ..default()
+            },
+            hud: HudSettings {
+                velocity_gauge: true,
+                hud_scale: 2.0,
+            },
+            performance: PerformanceSettings {
+                mode_override: Some(PerformanceMode::Low),
+            },
+        };
+
+        let loaded: SettingsFile = ron::from_str(&file.to_ron()).unwrap();
+        assert_eq!(loaded.to_ron(), file.to_ron());
+    }
+
+    #[test]
+    fn missing_settings_keep_their_defaults() {
+        let loaded: SettingsFile = migrate(ron::from_str("()").unwrap());
+        assert_eq!(loaded.to_ron(), SettingsFile::default().to_ron());
+        // Not saved at all, so it must come back as the default rather than zero
+        assert_eq!(loaded.render_scale.zoom, RenderScale::default().zoom);
+    }
+
+    /// A fresh directory for a test to put files in.
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("flappy-settings-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_file_is_reported_as_missing() {
+        let dir = test_dir("missing");
+        let result = load_settings_from(&dir.join("settings.ron"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(SettingsError::Missing)));
+    }
+
+    #[test]
+    fn corrupt_file_is_reported_as_corrupt() {
+        let dir = test_dir("corrupt");
+        let path = dir.join("settings.ron");
+        std::fs::write(&path, "(display: ").unwrap();
+        let result = load_settings_from(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(SettingsError::Corrupt(_))));
+    }
+
+    #[test]
+    fn unusable_path_is_reported_as_io_error() {
+        let dir = test_dir("io");
+        // A directory can't be read or written as a file
+        let read = load_settings_from(&dir);
+        let write = write_settings_to(&dir, "()");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(read, Err(SettingsError::Io(_))));
+        assert!(matches!(write, Err(SettingsError::Io(_))));
+        assert!(write.unwrap_err().source().is_some());
+    }
+
+    #[test]
+    fn written_settings_load_back() {
+        let dir = test_dir("write");
+        let path = dir.join("nested").join("settings.ron");
+        let file = SettingsFile {
+            physics_preset: PhysicsPreset::Modern,
+            ..default()
+        };
+        write_settings_to(&path, &file.to_ron()).unwrap();
+        let loaded = load_settings_from(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.physics_preset, PhysicsPreset::Modern);
+    }
+
+    #[test]
+    fn version_1_file_is_migrated() {
+        // Written before the version field and most of the other settings existed
+        let dir = test_dir("v1");
+        let path = dir.join("settings.ron");
+        std::fs::write(
+            &path,
+            "(display: (present_mode: Immediate, smooth_filtering: true, pixel_snap: false), \
+             fps_counter: (visible: true))",
+        )
+        .unwrap();
+        let loaded = load_settings_from(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.version, SETTINGS_VERSION);
+        assert!(loaded.display.smooth_filtering);
+        assert!(!loaded.display.pixel_snap);
+        assert!(loaded.fps_counter.visible);
+        assert_eq!(loaded.bindings.flap, KeyBindings::default().flap);
+        assert_eq!(loaded.physics_preset, PhysicsPreset::Classic);
+        assert_eq!(loaded.render_scale.camera_offset_y, 0.0);
+        assert_eq!(loaded.hud.hud_scale, 1.0);
+        assert!(!loaded.hud.velocity_gauge);
+    }
+}
@@ -0,0 +1,60 @@
+//! Headless harness for testing gameplay systems with a manually stepped clock.
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy, utils::Duration};
+
+use crate::{
+    Accessibility, AudioAvailability, GameState, PlayArea, Player, PlayerPlugin, RenderScale,
+};
+
+/// An app with the bird and its systems but no window, rendering or audio output. The bird has
+/// already been spawned on the menu.
+pub fn player_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin))
+        .add_state::<GameState>()
+        .init_resource::<PlayArea>()
+        .init_resource::<RenderScale>()
+        .init_resource::<Accessibility>()
+        .init_resource::<AudioAvailability>()
+        // Normally added by the render plugins
+        .add_asset::<Image>()
+        .add_plugins(PlayerPlugin);
+    // The first update runs startup and doesn't advance time
+    app.update();
+    app
+}
+
+/// Runs one frame that is `dt` seconds long.
+pub fn step(app: &mut App, dt: f32) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        dt,
+    )));
+    app.update();
+}
+
+/// Leaves the menu, as if the player had tapped to start.
+pub fn start_game(app: &mut App) {
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    step(app, 0.0);
+}
+
+/// Starts a game and lets the bird fall until it rests on the ground, returning its height.
+pub fn settle(app: &mut App) -> f32 {
+    start_game(app);
+    for _ in 0..60 {
+        step(app, 1.0 / 16.0);
+    }
+    player_transform(app).translation.y
+}
+
+pub fn player(app: &mut App) -> Mut<'_, Player> {
+    app.world.query::<&mut Player>().single_mut(&mut app.world)
+}
+
+pub fn player_transform(app: &mut App) -> Mut<'_, Transform> {
+    app.world
+        .query_filtered::<&mut Transform, With<Player>>()
+        .single_mut(&mut app.world)
+}
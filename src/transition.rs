@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::{Accessibility, GameState};
+
+/// Seconds to fade to black and back when changing state.
+pub const TRANSITION_TIME: f32 = 0.5;
+
+/// A state change in progress. The screen fades to black, the state changes at the midpoint and
+/// the screen fades back in. Flap input is ignored until it has finished.
+#[derive(Resource, Debug)]
+pub struct Transition {
+    pub from: GameState,
+    pub to: GameState,
+    timer: Timer,
+    swapped: bool,
+}
+
+impl Transition {
+    /// With reduced motion there is no fade and the state changes straight away.
+    pub fn new(from: GameState, to: GameState, accessibility: &Accessibility) -> Self {
+        let time = if accessibility.reduced_motion {
+            0.0
+        } else {
+            TRANSITION_TIME
+        };
+        Self {
+            from,
+            to,
+            timer: Timer::from_seconds(time, TimerMode::Once),
+            swapped: false,
+        }
+    }
+
+    /// How black the screen is, from `0.0` at either end of the fade to `1.0` at the midpoint.
+    fn darkness(&self) -> f32 {
+        1.0 - (self.timer.percent() * 2.0 - 1.0).abs()
+    }
+}
+
+#[derive(Component)]
+pub struct TransitionOverlay;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_transition_overlay)
+            .add_systems(Update, transition_system);
+    }
+}
+
+fn spawn_transition_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            // In front of the rest of the UI
+            z_index: ZIndex::Global(i32::MAX),
+            ..default()
+        },
+        TransitionOverlay,
+    ));
+}
+
+pub fn transition_system(
+    transition: Option<ResMut<Transition>>,
+    mut overlay: Query<&mut BackgroundColor, With<TransitionOverlay>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    if let Some(mut transition) = transition {
+        transition.timer.tick(time.delta());
+        if !transition.swapped && transition.timer.percent() >= 0.5 {
+            next_state.set(transition.to);
+            transition.swapped = true;
+        }
+
+        let darkness = if transition.timer.finished() {
+            commands.remove_resource::<Transition>();
+            0.0
+        } else {
+            transition.darkness()
+        };
+        if let Ok(mut color) = overlay.get_single_mut() {
+            color.0 = Color::rgba(0.0, 0.0, 0.0, darkness);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::*, FlapEvent};
+
+    fn transition_app(reduced_motion: bool) -> App {
+        let mut app = player_app();
+        app.insert_resource(Accessibility {
+            reduced_motion,
+            ..default()
+        })
+        .add_plugins(TransitionPlugin)
+        // Startup has already run, so spawn the overlay by hand
+        .add_systems(Update, spawn_transition_overlay.run_if(run_once()));
+        step(&mut app, 1.0 / 64.0);
+        app
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world.resource::<State<GameState>>().get()
+    }
+
+    fn darkness(app: &mut App) -> f32 {
+        app.world
+            .query_filtered::<&BackgroundColor, With<TransitionOverlay>>()
+            .single(&app.world)
+            .0
+            .a()
+    }
+
+    #[test]
+    fn tapping_on_the_menu_fades_into_the_game() {
+        let mut app = transition_app(false);
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 64.0);
+        assert!(app.world.contains_resource::<Transition>());
+
+        // Still on the menu while fading out
+        let frames = (TRANSITION_TIME * 64.0) as usize;
+        for _ in 0..frames / 2 - 2 {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert_eq!(state(&app), GameState::Menu);
+        assert!(darkness(&mut app) > 0.5);
+
+        for _ in 0..4 {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert_eq!(state(&app), GameState::Game);
+
+        for _ in 0..frames {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert!(!app.world.contains_resource::<Transition>());
+        assert_eq!(darkness(&mut app), 0.0);
+    }
+
+    #[test]
+    fn flaps_are_ignored_during_the_fade() {
+        let mut app = transition_app(false);
+        app.world.send_event(FlapEvent::default());
+        step(&mut app, 1.0 / 64.0);
+        let frames = (TRANSITION_TIME * 64.0) as usize;
+        for _ in 0..frames / 2 + 2 {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert_eq!(state(&app), GameState::Game);
+
+        app.world
+            .resource_mut::<Input<MouseButton>>()
+            .press(MouseButton::Left);
+        step(&mut app, 1.0 / 64.0);
+        assert!(player(&mut app).y_vel <= 0.0);
+    }
+
+    #[test]
+    fn reduced_motion_changes_state_without_a_fade() {
+        let mut app = transition_app(true);
+        app.world.send_event(FlapEvent::default());
+        // One frame to start the transition, one to finish it, and the state changes at the start
+        // of the next, as it would without a transition
+        for _ in 0..3 {
+            step(&mut app, 1.0 / 64.0);
+        }
+        assert_eq!(state(&app), GameState::Game);
+        assert!(!app.world.contains_resource::<Transition>());
+    }
+}
@@ -0,0 +1,36 @@
+//! Draw order of sprites in the world. Higher values are drawn on top.
+
+/// The camera looks down from here, so nothing above this is visible.
+pub const CAMERA: f32 = 999.9;
+
+pub const BACKGROUND: f32 = -1.0;
+/// Ambient particles spread over the `0.1` above this, with faster ones in front.
+pub const AMBIENT_PARTICLES: f32 = -0.5;
+pub const GROUND: f32 = -0.1;
+/// Just behind the bird but in front of the ground.
+pub const PUFF_PARTICLES: f32 = -0.05;
+pub const PLAYER: f32 = 0.0;
+pub const HUD: f32 = 5.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_are_in_draw_order() {
+        let layers = [
+            BACKGROUND,
+            AMBIENT_PARTICLES,
+            // The front of the ambient particles' spread
+            AMBIENT_PARTICLES + 0.1,
+            GROUND,
+            PUFF_PARTICLES,
+            PLAYER,
+            HUD,
+            CAMERA,
+        ];
+        for pair in layers.windows(2) {
+            assert!(pair[0] < pair[1], "{} is not below {}", pair[0], pair[1]);
+        }
+    }
+}